@@ -0,0 +1,62 @@
+// Force layout on a vault-sized graph: run with `cargo bench --bench layout`
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use dioxus_brain::graph::{GraphLayout, KnowledgeGraph};
+use dioxus_brain::store::AppState;
+
+const PAGES: usize = 5_000;
+const LINKS_PER_PAGE: usize = 2;
+
+/// A vault of `PAGES` pages, each linking to `LINKS_PER_PAGE` others picked
+/// by a fixed generator, so every run lays out the same graph
+fn vault_graph() -> KnowledgeGraph {
+    let mut state = AppState::default();
+    let ids: Vec<String> = (0..PAGES).map(|i| state.add_page(&format!("Page {}", i))).collect();
+
+    let mut seed: u64 = 42;
+    for (i, id) in ids.iter().enumerate() {
+        let links: Vec<String> = (0..LINKS_PER_PAGE)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let target = (seed >> 33) as usize % PAGES;
+                let target = if target == i { (i + 1) % PAGES } else { target };
+                format!("[[Page {}]]", target)
+            })
+            .collect();
+        state.insert_outline(id, &format!("- {}", links.join(" ")));
+    }
+
+    let mut graph = KnowledgeGraph::default();
+    graph.build_from_state(&state.pages, &state.blocks, None);
+    graph
+}
+
+fn layout(c: &mut Criterion) {
+    let graph = vault_graph();
+    let mut group = c.benchmark_group("layout 5k nodes 10k edges");
+    group.sample_size(20);
+
+    group.bench_function("sync", |b| {
+        b.iter(|| {
+            let mut layout = GraphLayout::new();
+            layout.sync(&graph, 800.0, 600.0);
+            layout
+        })
+    });
+
+    // Tick a spread-out layout at full temperature
+    let mut layout = GraphLayout::new();
+    layout.sync(&graph, 800.0, 600.0);
+    layout.run(50);
+    group.bench_function("tick", |b| {
+        b.iter(|| {
+            layout.alpha = 1.0;
+            layout.tick();
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, layout);
+criterion_main!(benches);
@@ -0,0 +1,355 @@
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+use crate::graph::KnowledgeGraph;
+use crate::store::{AppState, Page};
+use crate::utils::fuzzy_score;
+
+/// Maximum number of suggestions shown
+pub const SUGGESTION_LIMIT: usize = 8;
+
+/// A `#tag` being typed at the end of the text. At least one character,
+/// so a `# ` heading doesn't open the popup.
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\s)(#([^\s#\[\]()]+))$").unwrap());
+
+/// What was typed to open the popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerKind {
+    /// `[[`
+    PageRef,
+    /// `#`
+    Tag,
+    /// `((`
+    BlockRef,
+}
+
+/// An open `[[`, `#` or `((` before the caret
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trigger {
+    pub kind: TriggerKind,
+    pub query: String,
+    /// Bytes replaced on completion, including an auto-closed `]]` or `))`
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionKind {
+    Page,
+    Alias,
+    Tag,
+    Block,
+    NewPage,
+}
+
+/// A completion offered in the popup
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub label: String,
+    /// Secondary text: the aliased page or the page holding a block
+    pub detail: Option<String>,
+    /// Text that replaces the trigger
+    pub insert: String,
+}
+
+/// Find the trigger the caret is in, looking at the text before `caret`
+pub fn find_trigger(text: &str, caret: usize) -> Option<Trigger> {
+    let caret = caret.min(text.len());
+    let before = text.get(..caret)?;
+    let after = &text[caret..];
+
+    let open = |opener: &str, closer: &str| -> Option<(usize, String)> {
+        let start = before.rfind(opener)?;
+        let query = &before[start + opener.len()..];
+        if query.contains(closer) || query.contains('\n') {
+            return None;
+        }
+        Some((start, query.to_string()))
+    };
+
+    let (kind, start, query, closer) = if let Some((start, query)) = open("[[", "]]") {
+        (TriggerKind::PageRef, start, query, "]]")
+    } else if let Some((start, query)) = open("((", "))") {
+        (TriggerKind::BlockRef, start, query, "))")
+    } else {
+        let cap = TAG_RE.captures(before)?;
+        (TriggerKind::Tag, cap.get(1)?.start(), cap.get(2)?.as_str().to_string(), "")
+    };
+
+    // An auto-closed `]]`/`))` right after the caret is replaced too
+    let end = if !closer.is_empty() && after.starts_with(closer) { caret + closer.len() } else { caret };
+    Some(Trigger { kind, query, range: start..end })
+}
+
+/// Suggestions for a trigger, best first
+pub fn suggest(state: &AppState, trigger: &Trigger) -> Vec<Suggestion> {
+    match trigger.kind {
+        TriggerKind::PageRef => suggest_pages(state, &trigger.query),
+        TriggerKind::Tag => suggest_tags(state, &trigger.query),
+        TriggerKind::BlockRef => suggest_blocks(state, &trigger.query),
+    }
+}
+
+/// Replace the trigger with a suggestion; returns the new text and caret
+pub fn apply_suggestion(text: &str, trigger: &Trigger, suggestion: &Suggestion) -> (String, usize) {
+    let new_text = format!("{}{}{}", &text[..trigger.range.start], suggestion.insert, &text[trigger.range.end..]);
+    (new_text, trigger.range.start + suggestion.insert.len())
+}
+
+/// Pages edited recently rank higher: up to 20 points, one less per day
+fn recency_bonus(page: &Page) -> i32 {
+    let days = (chrono::Utc::now() - page.updated_at).num_days().max(0);
+    (20 - days.min(20)) as i32
+}
+
+fn page_aliases(page: &Page) -> Vec<String> {
+    page.properties
+        .get("alias")
+        .map(|aliases| {
+            aliases
+                .split(',')
+                .map(|a| a.trim().trim_start_matches("[[").trim_end_matches("]]").to_string())
+                .filter(|a| !a.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Pages and aliases matching `query`, ranked by fuzzy match plus recency
+fn ranked_pages(state: &AppState, query: &str) -> Vec<(i32, Suggestion)> {
+    let mut ranked = Vec::new();
+
+    for page in state.pages.values() {
+        let recency = recency_bonus(page);
+        let title_score = if query.is_empty() { Some(0) } else { fuzzy_score(query, &page.title) };
+        if let Some(score) = title_score {
+            ranked.push((score + recency, Suggestion {
+                kind: SuggestionKind::Page,
+                label: page.title.clone(),
+                detail: None,
+                insert: format!("[[{}]]", page.title),
+            }));
+        }
+
+        if query.is_empty() {
+            continue;
+        }
+        for alias in page_aliases(page) {
+            if let Some(score) = fuzzy_score(query, &alias) {
+                ranked.push((score + recency, Suggestion {
+                    kind: SuggestionKind::Alias,
+                    label: alias.clone(),
+                    detail: Some(page.title.clone()),
+                    insert: format!("[[{}]]", alias),
+                }));
+            }
+        }
+    }
+
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.label.cmp(&b.1.label)));
+    ranked
+}
+
+fn suggest_pages(state: &AppState, query: &str) -> Vec<Suggestion> {
+    let query = query.trim();
+    let mut suggestions: Vec<Suggestion> = ranked_pages(state, query)
+        .into_iter()
+        .map(|(_, s)| s)
+        .take(SUGGESTION_LIMIT)
+        .collect();
+
+    let exists = state.find_page_by_title(query).is_some()
+        || suggestions.iter().any(|s| s.label.eq_ignore_ascii_case(query));
+    if !query.is_empty() && !exists {
+        suggestions.push(Suggestion {
+            kind: SuggestionKind::NewPage,
+            label: format!("Create page \"{}\"", query),
+            detail: None,
+            insert: format!("[[{}]]", query),
+        });
+    }
+    suggestions
+}
+
+/// `#tag` for single words, `#[[multi word]]` otherwise
+fn tag_text(name: &str) -> String {
+    if name.chars().any(char::is_whitespace) {
+        format!("#[[{}]]", name)
+    } else {
+        format!("#{}", name)
+    }
+}
+
+fn suggest_tags(state: &AppState, query: &str) -> Vec<Suggestion> {
+    let mut ranked: Vec<(i32, Suggestion)> = KnowledgeGraph::extract_tags(&state.pages, &state.blocks)
+        .into_iter()
+        .filter_map(|tag| {
+            let name = tag.trim_start_matches('#').to_string();
+            let score = if query.is_empty() { Some(0) } else { fuzzy_score(query, &name) }?;
+            Some((score, Suggestion {
+                kind: SuggestionKind::Tag,
+                insert: tag_text(&name),
+                label: name,
+                detail: None,
+            }))
+        })
+        .collect();
+
+    // Page titles can be used as tags too
+    for (score, page) in ranked_pages(state, query) {
+        if page.kind == SuggestionKind::Page && !ranked.iter().any(|(_, t)| t.label.eq_ignore_ascii_case(&page.label)) {
+            ranked.push((score - 5, Suggestion { insert: tag_text(&page.label), ..page }));
+        }
+    }
+
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.label.cmp(&b.1.label)));
+    let mut suggestions: Vec<Suggestion> = ranked.into_iter().map(|(_, s)| s).take(SUGGESTION_LIMIT).collect();
+
+    let query = query.trim();
+    if !query.is_empty() && !suggestions.iter().any(|s| s.label.eq_ignore_ascii_case(query)) {
+        suggestions.push(Suggestion {
+            kind: SuggestionKind::NewPage,
+            label: format!("Create page \"{}\"", query),
+            detail: None,
+            insert: tag_text(query),
+        });
+    }
+    suggestions
+}
+
+fn suggest_blocks(state: &AppState, query: &str) -> Vec<Suggestion> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<(usize, chrono::DateTime<chrono::Utc>, Suggestion)> = state.blocks.values()
+        .filter_map(|block| {
+            let (_, body) = crate::markdown::split_properties(&block.content);
+            let text = crate::markdown::inline::plain_text(&crate::markdown::parse_inline(body.trim()));
+            let position = text.to_lowercase().find(&query)?;
+            let label: String = text.lines().next().unwrap_or_default().chars().take(80).collect();
+            Some((position, block.updated_at, Suggestion {
+                kind: SuggestionKind::Block,
+                label,
+                detail: state.find_page_for_block(&block.id).map(|p| p.title.clone()),
+                insert: format!("(({}))", block.id),
+            }))
+        })
+        .collect();
+
+    // Earlier matches first, then most recently edited
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    ranked.into_iter().map(|(_, _, s)| s).take(SUGGESTION_LIMIT).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(text: &str) -> Option<Trigger> {
+        find_trigger(text, text.len())
+    }
+
+    fn labels(suggestions: &[Suggestion]) -> Vec<&str> {
+        suggestions.iter().map(|s| s.label.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_each_trigger_before_the_caret() {
+        assert_eq!(trigger("see [[Ru"), Some(Trigger { kind: TriggerKind::PageRef, query: "Ru".into(), range: 4..8 }));
+        assert_eq!(trigger("ref ((abc"), Some(Trigger { kind: TriggerKind::BlockRef, query: "abc".into(), range: 4..9 }));
+        assert_eq!(trigger("note #ru"), Some(Trigger { kind: TriggerKind::Tag, query: "ru".into(), range: 5..8 }));
+        assert_eq!(trigger("#ru"), Some(Trigger { kind: TriggerKind::Tag, query: "ru".into(), range: 0..3 }));
+
+        // An auto-closed `]]` after the caret is part of the range
+        let closed = find_trigger("see [[Ru]] now", 8).unwrap();
+        assert_eq!((closed.query.as_str(), closed.range), ("Ru", 4..10));
+    }
+
+    #[test]
+    fn closed_or_broken_triggers_are_ignored() {
+        assert_eq!(trigger("[[A]] and "), None);
+        assert_eq!(trigger("((id)) "), None);
+        assert_eq!(trigger("[[two\nlines"), None);
+        assert_eq!(trigger("# heading"), None);
+        assert_eq!(trigger("issue#12"), None);
+        // A closed link before an open tag leaves the tag
+        assert_eq!(trigger("[[A]] #ta").map(|t| (t.kind, t.query)), Some((TriggerKind::Tag, "ta".into())));
+        assert_eq!(find_trigger("[[A", 1), None);
+    }
+
+    #[test]
+    fn pages_rank_by_match_then_recency() {
+        let mut state = AppState::default();
+        state.create_page("Rare utensils stash");
+        state.create_page("Rust");
+        let old = state.create_page("Rust Book");
+        state.create_page("Rusty Tools");
+        state.pages.get_mut(&old).unwrap().updated_at = chrono::Utc::now() - chrono::Duration::days(30);
+
+        let found = suggest(&state, &trigger("[[rust").unwrap());
+        assert_eq!(labels(&found), ["Rust", "Rusty Tools", "Rust Book", "Rare utensils stash"]);
+        assert_eq!(found[0].insert, "[[Rust]]");
+        // An exact title means nothing to create
+        assert!(found.iter().all(|s| s.kind != SuggestionKind::NewPage));
+    }
+
+    #[test]
+    fn create_page_comes_last() {
+        let mut state = AppState::default();
+        for i in 0..SUGGESTION_LIMIT + 2 {
+            state.create_page(&format!("Note {}", i));
+        }
+        let found = suggest(&state, &trigger("[[note").unwrap());
+        assert_eq!(found.len(), SUGGESTION_LIMIT + 1);
+        let last = found.last().unwrap();
+        assert_eq!((last.kind, last.label.as_str(), last.insert.as_str()), (SuggestionKind::NewPage, "Create page \"note\"", "[[note]]"));
+        assert!(found[..SUGGESTION_LIMIT].iter().all(|s| s.kind == SuggestionKind::Page));
+    }
+
+    #[test]
+    fn aliases_point_at_their_page() {
+        let mut state = AppState::default();
+        let id = state.create_page("Rust Programming Language");
+        state.pages.get_mut(&id).unwrap().properties.insert("alias".into(), "[[RustLang]], rs".into());
+        let found = suggest(&state, &trigger("[[rustl").unwrap());
+        let alias = found.iter().find(|s| s.kind == SuggestionKind::Alias).unwrap();
+        assert_eq!(alias.label, "RustLang");
+        assert_eq!(alias.detail.as_deref(), Some("Rust Programming Language"));
+        assert_eq!(alias.insert, "[[RustLang]]");
+    }
+
+    #[test]
+    fn tags_and_blocks() {
+        let mut state = AppState::default();
+        let page = state.create_page("Inbox");
+        let blocks = state.insert_outline(&page, "- buy milk #groceries #[[weekly plan]]\n- call Sam");
+
+        let tags = suggest(&state, &trigger("list #groc").unwrap());
+        assert_eq!((tags[0].kind, tags[0].insert.as_str()), (SuggestionKind::Tag, "#groceries"));
+        let tags = suggest(&state, &trigger("#weekly").unwrap());
+        assert_eq!(tags[0].insert, "#[[weekly plan]]");
+
+        let found = suggest(&state, &trigger("see ((milk").unwrap());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].insert, format!("(({}))", blocks[0]));
+        assert_eq!(found[0].detail.as_deref(), Some("Inbox"));
+        assert!(suggest(&state, &trigger("((").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn applying_places_the_caret_after_the_insertion() {
+        let text = "see [[Ru]] now";
+        let trigger = find_trigger(text, 8).unwrap();
+        let suggestion = Suggestion { kind: SuggestionKind::Page, label: "Rust".into(), detail: None, insert: "[[Rust]]".into() };
+        let (text, caret) = apply_suggestion(text, &trigger, &suggestion);
+        assert_eq!(text, "see [[Rust]] now");
+        assert_eq!(&text[caret..], " now");
+
+        let text = "ünï #ta";
+        let trigger = find_trigger(text, text.len()).unwrap();
+        let suggestion = Suggestion { kind: SuggestionKind::Tag, label: "task".into(), detail: None, insert: "#task".into() };
+        assert_eq!(apply_suggestion(text, &trigger, &suggestion), ("ünï #task".to_string(), "ünï #task".len()));
+    }
+}
@@ -3,12 +3,47 @@ use dioxus::prelude::*;
 pub mod sidebar;
 pub mod editor;
 pub mod block;
+pub mod inline;
+pub mod markdown;
+pub mod code_block;
 pub mod backlinks;
 pub mod graph;
+pub mod local_graph;
 pub mod command_palette;
+pub mod journals;
+pub mod calendar;
+pub mod date_picker;
+pub mod notifications;
+pub mod tasks;
+pub mod slash_menu;
+pub mod autocomplete;
+pub mod selection;
+pub mod zoom;
+pub mod page_view;
+pub mod search;
+pub mod all_pages;
+pub mod workspace;
+pub mod right_sidebar;
 
 pub use sidebar::Sidebar;
 pub use editor::Editor;
-pub use backlinks::BacklinksPanel;
+pub use backlinks::{BacklinksPanel, OutlinksPanel};
 pub use graph::GraphView;
+pub use local_graph::LocalGraph;
 pub use command_palette::CommandPalette;
+pub use journals::JournalsView;
+pub use calendar::Calendar;
+pub use date_picker::DateSuggestions;
+pub use notifications::NotificationTray;
+pub use tasks::TasksView;
+pub use slash_menu::SlashMenu;
+pub use autocomplete::AutocompletePopup;
+pub use selection::SelectionToolbar;
+pub use zoom::BlockZoom;
+pub use page_view::PageView;
+pub use search::SearchView;
+pub use all_pages::AllPagesView;
+pub use workspace::WorkspaceView;
+pub use right_sidebar::RightSidebar;
+pub use inline::InlineContent;
+pub use markdown::MarkdownContent;
@@ -0,0 +1,451 @@
+// Graph analytics
+// PageRank, betweenness, components, Louvain communities and link suggestions
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use super::KnowledgeGraph;
+
+/// PageRank damping: the chance a reader follows a link instead of jumping
+const DAMPING: f64 = 0.85;
+const PAGE_RANK_ITERATIONS: usize = 100;
+const PAGE_RANK_TOLERANCE: f64 = 1e-6;
+/// Louvain levels to aggregate before giving up
+const MAX_LEVELS: usize = 32;
+
+/// A link worth adding between two pages that are not linked yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkSuggestion {
+    pub source: String,
+    pub target: String,
+    /// Pages linked with both
+    pub shared_neighbors: usize,
+    pub shared_tags: Vec<String>,
+    pub score: f64,
+}
+
+/// Everything the insights panel shows about a graph
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphInsights {
+    pub page_rank: HashMap<String, f64>,
+    /// Share of shortest paths through each page, 0 to 1
+    pub betweenness: HashMap<String, f64>,
+    /// Connected groups of pages, largest first
+    pub components: Vec<Vec<String>>,
+    /// Community of each page, numbered from the largest
+    pub communities: HashMap<String, usize>,
+    pub community_count: usize,
+}
+
+impl GraphInsights {
+    /// Highest scores first
+    pub fn top(scores: &HashMap<String, f64>, limit: usize) -> Vec<(String, f64)> {
+        let mut top: Vec<(String, f64)> = scores.iter().map(|(id, s)| (id.clone(), *s)).collect();
+        top.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit);
+        top
+    }
+}
+
+impl KnowledgeGraph {
+    /// PageRank, betweenness, components and communities in one go
+    pub fn insights(&self) -> GraphInsights {
+        let communities = self.communities();
+        let community_count = communities.values().max().map(|c| c + 1).unwrap_or(0);
+        GraphInsights {
+            page_rank: self.page_rank(),
+            betweenness: self.betweenness(),
+            components: self.connected_components(),
+            communities,
+            community_count,
+        }
+    }
+
+    /// PageRank over the directed, weighted links. Pages without outlinks
+    /// spread their rank over every page.
+    pub fn page_rank(&self) -> HashMap<String, f64> {
+        let (ids, index) = self.sorted_index();
+        let n = ids.len();
+        if n == 0 {
+            return HashMap::new();
+        }
+
+        let mut out_weight = vec![0.0; n];
+        let mut incoming: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+        for edge in &self.edges {
+            if let (Some(&s), Some(&t)) = (index.get(edge.source.as_str()), index.get(edge.target.as_str())) {
+                out_weight[s] += edge.weight as f64;
+                incoming[t].push((s, edge.weight as f64));
+            }
+        }
+
+        let mut rank = vec![1.0 / n as f64; n];
+        for _ in 0..PAGE_RANK_ITERATIONS {
+            let dangling: f64 = (0..n).filter(|&i| out_weight[i] == 0.0).map(|i| rank[i]).sum();
+            let base = (1.0 - DAMPING) / n as f64 + DAMPING * dangling / n as f64;
+            let next: Vec<f64> = incoming.iter()
+                .map(|links| base + DAMPING * links.iter().map(|&(s, w)| rank[s] * w / out_weight[s]).sum::<f64>())
+                .collect();
+            let change: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+            rank = next;
+            if change < PAGE_RANK_TOLERANCE {
+                break;
+            }
+        }
+
+        ids.into_iter().map(|id| id.to_string()).zip(rank).collect()
+    }
+
+    /// Betweenness centrality (Brandes) with links taken both ways,
+    /// normalized so a page on every shortest path scores 1
+    pub fn betweenness(&self) -> HashMap<String, f64> {
+        let (ids, index) = self.sorted_index();
+        let n = ids.len();
+        let neighbors = self.undirected_neighbors(&ids, &index);
+
+        let mut centrality = vec![0.0; n];
+        let mut order: Vec<usize> = Vec::with_capacity(n);
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut paths = vec![0.0; n];
+        let mut distance: Vec<i64> = vec![-1; n];
+        let mut dependency = vec![0.0; n];
+        let mut queue = VecDeque::new();
+
+        for source in 0..n {
+            order.clear();
+            for i in 0..n {
+                predecessors[i].clear();
+                paths[i] = 0.0;
+                distance[i] = -1;
+                dependency[i] = 0.0;
+            }
+            paths[source] = 1.0;
+            distance[source] = 0;
+            queue.push_back(source);
+
+            while let Some(v) = queue.pop_front() {
+                order.push(v);
+                for &w in &neighbors[v] {
+                    if distance[w] < 0 {
+                        distance[w] = distance[v] + 1;
+                        queue.push_back(w);
+                    }
+                    if distance[w] == distance[v] + 1 {
+                        paths[w] += paths[v];
+                        predecessors[w].push(v);
+                    }
+                }
+            }
+
+            while let Some(w) = order.pop() {
+                for &v in &predecessors[w] {
+                    dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+                }
+                if w != source {
+                    centrality[w] += dependency[w];
+                }
+            }
+        }
+
+        // Every pair was counted from both ends
+        let pairs = if n > 2 { ((n - 1) * (n - 2)) as f64 } else { 1.0 };
+        ids.into_iter().map(|id| id.to_string()).zip(centrality.into_iter().map(|c| c / pairs)).collect()
+    }
+
+    /// Groups of pages connected by links in either direction, largest first
+    pub fn connected_components(&self) -> Vec<Vec<String>> {
+        let (ids, index) = self.sorted_index();
+        let neighbors = self.undirected_neighbors(&ids, &index);
+        let mut seen = vec![false; ids.len()];
+        let mut components = Vec::new();
+
+        for start in 0..ids.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut component = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            while let Some(v) = queue.pop_front() {
+                component.push(ids[v].to_string());
+                for &w in &neighbors[v] {
+                    if !seen[w] {
+                        seen[w] = true;
+                        queue.push_back(w);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        components
+    }
+
+    /// Communities found by Louvain modularity optimization over the
+    /// weighted links, numbered from the largest community down
+    pub fn communities(&self) -> HashMap<String, usize> {
+        let (ids, index) = self.sorted_index();
+        let n = ids.len();
+
+        // Symmetric weights; a self loop holds twice the weight inside a node
+        let mut weights: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); n];
+        for edge in &self.edges {
+            if let (Some(&s), Some(&t)) = (index.get(edge.source.as_str()), index.get(edge.target.as_str())) {
+                if s != t {
+                    *weights[s].entry(t).or_default() += edge.weight as f64;
+                    *weights[t].entry(s).or_default() += edge.weight as f64;
+                }
+            }
+        }
+
+        // Community of every page, refined one level at a time
+        let mut membership: Vec<usize> = (0..n).collect();
+        for _ in 0..MAX_LEVELS {
+            let (assignment, count) = louvain_pass(&weights);
+            if count == weights.len() {
+                break;
+            }
+            for community in membership.iter_mut() {
+                *community = assignment[*community];
+            }
+            weights = aggregate(&weights, &assignment, count);
+        }
+
+        // Number by size so the largest communities get the first colors
+        let mut sizes: HashMap<usize, usize> = HashMap::new();
+        for &c in &membership {
+            *sizes.entry(c).or_default() += 1;
+        }
+        let mut ranked: Vec<(usize, usize)> = sizes.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let renumber: HashMap<usize, usize> = ranked.iter().enumerate().map(|(i, (c, _))| (*c, i)).collect();
+
+        ids.into_iter().zip(membership).map(|(id, c)| (id.to_string(), renumber[&c])).collect()
+    }
+
+    /// Pairs of unlinked pages that share neighbors or tags, best first.
+    /// With `page_id`, only suggestions for that page.
+    pub fn suggest_links(&self, page_id: Option<&str>, limit: usize) -> Vec<LinkSuggestion> {
+        let mut tagged: HashMap<String, Vec<&str>> = HashMap::new();
+        for node in self.nodes.values() {
+            for tag in &node.tags {
+                tagged.entry(tag.to_lowercase()).or_default().push(node.id.as_str());
+            }
+        }
+        let empty = HashSet::new();
+
+        let mut sources: Vec<&str> = match page_id {
+            Some(id) if self.nodes.contains_key(id) => vec![id],
+            Some(_) => Vec::new(),
+            None => self.nodes.keys().map(|id| id.as_str()).collect(),
+        };
+        sources.sort();
+
+        let mut suggestions = Vec::new();
+        for source in sources {
+            let linked = self.adjacency.get(source).unwrap_or(&empty);
+            let source_tags: HashSet<String> = self.nodes[source].tags.iter().map(|t| t.to_lowercase()).collect();
+
+            // Pages two hops away or sharing a tag
+            let mut candidates: HashSet<&str> = linked.iter()
+                .filter_map(|n| self.adjacency.get(n))
+                .flatten()
+                .map(|id| id.as_str())
+                .collect();
+            for tag in &source_tags {
+                candidates.extend(tagged.get(tag).into_iter().flatten().copied());
+            }
+
+            for target in candidates {
+                // Each pair once when looking at the whole graph
+                if target == source || linked.contains(target) || (page_id.is_none() && target < source) {
+                    continue;
+                }
+                let target_linked = self.adjacency.get(target).unwrap_or(&empty);
+                let common: Vec<&String> = linked.intersection(target_linked).collect();
+                let mut shared_tags: Vec<String> = self.nodes[target].tags.iter()
+                    .map(|t| t.to_lowercase())
+                    .filter(|t| source_tags.contains(t))
+                    .collect();
+                shared_tags.sort();
+                shared_tags.dedup();
+                if common.len() + shared_tags.len() < 2 {
+                    continue;
+                }
+
+                // Adamic-Adar: a neighbor shared with few other pages says more
+                let score = common.iter()
+                    .map(|n| 1.0 / (self.adjacency.get(*n).map(|s| s.len()).unwrap_or(2) as f64).ln().max(0.5))
+                    .sum::<f64>()
+                    + shared_tags.len() as f64;
+                suggestions.push(LinkSuggestion {
+                    source: source.to_string(),
+                    target: target.to_string(),
+                    shared_neighbors: common.len(),
+                    shared_tags,
+                    score,
+                });
+            }
+        }
+
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score)
+            .then_with(|| a.source.cmp(&b.source))
+            .then_with(|| a.target.cmp(&b.target)));
+        suggestions.truncate(limit);
+        suggestions
+    }
+
+    /// Node ids in a stable order, with their positions
+    fn sorted_index(&self) -> (Vec<&str>, HashMap<&str, usize>) {
+        let mut ids: Vec<&str> = self.nodes.keys().map(|id| id.as_str()).collect();
+        ids.sort();
+        let index = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        (ids, index)
+    }
+
+    fn undirected_neighbors(&self, ids: &[&str], index: &HashMap<&str, usize>) -> Vec<Vec<usize>> {
+        ids.iter()
+            .map(|id| {
+                let mut neighbors: Vec<usize> = self.adjacency.get(*id).into_iter()
+                    .flatten()
+                    .filter_map(|n| index.get(n.as_str()).copied())
+                    .collect();
+                neighbors.sort();
+                neighbors
+            })
+            .collect()
+    }
+}
+
+/// Move nodes between communities while modularity improves; returns each
+/// node's community, numbered from 0, and the number of communities
+fn louvain_pass(weights: &[BTreeMap<usize, f64>]) -> (Vec<usize>, usize) {
+    let n = weights.len();
+    let degree: Vec<f64> = weights.iter().map(|w| w.values().sum()).collect();
+    let total: f64 = degree.iter().sum();
+    if total == 0.0 {
+        return ((0..n).collect(), n);
+    }
+
+    let mut community: Vec<usize> = (0..n).collect();
+    let mut community_degree = degree.clone();
+    loop {
+        let mut moved = false;
+        for i in 0..n {
+            let current = community[i];
+            let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+            for (&j, &w) in &weights[i] {
+                if j != i {
+                    *links.entry(community[j]).or_default() += w;
+                }
+            }
+
+            // Take the node out, then put it where the modularity gain is largest
+            community_degree[current] -= degree[i];
+            let gain = |c: usize, w: f64| w - community_degree[c] * degree[i] / total;
+            let mut best = current;
+            let mut best_gain = gain(current, links.get(&current).copied().unwrap_or(0.0));
+            for (&c, &w) in &links {
+                let g = gain(c, w);
+                if g > best_gain + 1e-12 {
+                    best = c;
+                    best_gain = g;
+                }
+            }
+            community_degree[best] += degree[i];
+            if best != current {
+                community[i] = best;
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    let mut numbers: HashMap<usize, usize> = HashMap::new();
+    let assignment: Vec<usize> = community.iter()
+        .map(|c| {
+            let next = numbers.len();
+            *numbers.entry(*c).or_insert(next)
+        })
+        .collect();
+    (assignment, numbers.len())
+}
+
+/// Collapse each community into one node
+fn aggregate(weights: &[BTreeMap<usize, f64>], assignment: &[usize], count: usize) -> Vec<BTreeMap<usize, f64>> {
+    let mut merged: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); count];
+    for (i, row) in weights.iter().enumerate() {
+        for (&j, &w) in row {
+            *merged[assignment[i]].entry(assignment[j]).or_default() += w;
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::graph;
+
+    /// `n0`..`n3` and `n4`..`n7` fully linked, with one link between them
+    fn bridged_cliques() -> KnowledgeGraph {
+        let mut links = Vec::new();
+        for group in [0, 4] {
+            for a in group..group + 4 {
+                for b in a + 1..group + 4 {
+                    links.push((a, b));
+                }
+            }
+        }
+        links.push((3, 4));
+        graph(8, &links)
+    }
+
+    #[test]
+    fn page_rank_sums_to_one() {
+        // A cycle, a dangling page and an isolated one
+        let graph = graph(5, &[(0, 1), (1, 2), (2, 0), (2, 3)]);
+        let rank = graph.page_rank();
+        assert_eq!(rank.len(), 5);
+        assert!((rank.values().sum::<f64>() - 1.0).abs() < 1e-6);
+        assert!(rank["n2"] > rank["n4"]);
+    }
+
+    #[test]
+    fn star_center_has_betweenness_one() {
+        let graph = graph(6, &[(0, 1), (0, 2), (3, 0), (4, 0), (0, 5)]);
+        let betweenness = graph.betweenness();
+        assert!((betweenness["n0"] - 1.0).abs() < 1e-9);
+        for leaf in 1..6 {
+            assert_eq!(betweenness[&format!("n{}", leaf)], 0.0);
+        }
+    }
+
+    #[test]
+    fn bridged_cliques_form_two_communities() {
+        let communities = bridged_cliques().communities();
+        let of = |i: usize| communities[&format!("n{}", i)];
+        assert!((0..4).all(|i| of(i) == of(0)));
+        assert!((4..8).all(|i| of(i) == of(4)));
+        assert_ne!(of(0), of(4));
+    }
+
+    #[test]
+    fn suggestions_are_never_already_linked() {
+        let mut graph = bridged_cliques();
+        // Pages sharing neighbors across the bridge and a pair sharing a tag
+        graph.nodes.get_mut("n1").unwrap().tags = vec!["rust".to_string(), "async".to_string()];
+        graph.nodes.get_mut("n6").unwrap().tags = vec!["Rust".to_string(), "async".to_string()];
+
+        let all = graph.suggest_links(None, 100);
+        assert!(all.iter().any(|s| (s.source.as_str(), s.target.as_str()) == ("n1", "n6")));
+        for page in std::iter::once(None).chain((0..8).map(|i| Some(format!("n{}", i)))) {
+            for suggestion in graph.suggest_links(page.as_deref(), 100) {
+                assert_ne!(suggestion.source, suggestion.target);
+                assert!(!graph.adjacency[&suggestion.source].contains(&suggestion.target), "{:?}", suggestion);
+            }
+        }
+    }
+}
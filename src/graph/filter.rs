@@ -0,0 +1,399 @@
+// Graph view filters and appearance
+// Page queries, color groups, node sizing and saved presets
+
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::markdown::{collect_tags, parse_inline, split_properties};
+use crate::store::{Block, Page};
+use super::KnowledgeGraph;
+
+/// What filter and group queries look at for one page
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageFacts {
+    /// Lowercased title
+    pub title: String,
+    /// Lowercased page tags and tags used in its blocks, sorted
+    pub tags: Vec<String>,
+    /// Page and block properties with lowercased keys and values; values of
+    /// a key set in several places are joined with commas
+    pub properties: HashMap<String, String>,
+    pub is_journal: bool,
+    /// Lowercased text of every block on the page
+    pub text: String,
+    pub word_count: usize,
+    /// Pages linking here
+    pub inbound: usize,
+    /// Pages linked from here
+    pub outbound: usize,
+}
+
+impl PageFacts {
+    pub fn is_orphan(&self) -> bool {
+        self.inbound == 0 && self.outbound == 0
+    }
+}
+
+/// Facts for every page in the graph
+pub fn page_facts(pages: &HashMap<String, Page>, blocks: &HashMap<String, Block>, graph: &KnowledgeGraph) -> HashMap<String, PageFacts> {
+    pages.values()
+        .filter(|page| graph.nodes.contains_key(&page.id))
+        .map(|page| {
+            let mut tags: Vec<String> = page.tags.iter().map(|t| t.to_lowercase()).collect();
+            let mut properties: HashMap<String, String> = page.properties.iter()
+                .map(|(k, v)| (k.to_lowercase(), v.to_lowercase()))
+                .collect();
+
+            // Every block of the page, nested ones included
+            let mut text = String::new();
+            let mut stack: Vec<&String> = page.blocks.iter().rev().collect();
+            while let Some(block_id) = stack.pop() {
+                if let Some(block) = blocks.get(block_id) {
+                    let (block_properties, body) = split_properties(&block.content);
+                    for (key, value) in block_properties {
+                        let (key, value) = (key.to_lowercase(), value.to_lowercase());
+                        if key == "tags" {
+                            tags.extend(value.split(',')
+                                .map(|t| t.trim().trim_start_matches('#').trim_start_matches("[[").trim_end_matches("]]").to_string())
+                                .filter(|t| !t.is_empty()));
+                        }
+                        properties.entry(key)
+                            .and_modify(|existing| {
+                                existing.push_str(", ");
+                                existing.push_str(&value);
+                            })
+                            .or_insert(value);
+                    }
+                    tags.extend(collect_tags(&parse_inline(&body)).into_iter().map(|t| t.to_lowercase()));
+
+                    text.push_str(&block.content);
+                    text.push('\n');
+                    stack.extend(block.children.iter().rev());
+                }
+            }
+            tags.sort();
+            tags.dedup();
+
+            let facts = PageFacts {
+                title: page.title.to_lowercase(),
+                tags,
+                properties,
+                is_journal: page.is_journal(),
+                word_count: text.split_whitespace().count(),
+                text: text.to_lowercase(),
+                inbound: graph.incoming.get(&page.id).map(|s| s.len()).unwrap_or(0),
+                outbound: graph.outgoing.get(&page.id).map(|s| s.len()).unwrap_or(0),
+            };
+            (page.id.clone(), facts)
+        })
+        .collect()
+}
+
+/// One condition of a graph query
+#[derive(Debug, Clone, PartialEq)]
+enum QueryTerm {
+    /// Title or page text contains the word
+    Text(String),
+    /// `tag:name` or `#name`: a page tag or a whole `#tag` in the page
+    Tag(String),
+    /// `path:area/project`: the page is in the namespace or is the page itself
+    Path(String),
+    /// `prop:key` or `prop:key=value`
+    Property(String, Option<String>),
+    /// `is:journal`
+    Journal,
+    /// `is:orphan`
+    Orphan,
+}
+
+/// A graph query: terms separated by spaces that must all match. A leading
+/// `-` negates a term; double quotes keep spaces inside one term.
+///
+/// `tag:project -path:archive "release plan"` matches pages tagged
+/// `project` outside the `archive/` namespace that mention "release plan".
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphQuery {
+    terms: Vec<(bool, QueryTerm)>,
+}
+
+impl GraphQuery {
+    pub fn parse(query: &str) -> Self {
+        let terms = split_terms(query).into_iter()
+            .filter_map(|word| {
+                let (negated, word) = match word.strip_prefix('-') {
+                    Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+                    _ => (false, word),
+                };
+                let lower = word.to_lowercase();
+                let term = if let Some(tag) = lower.strip_prefix("tag:").or_else(|| lower.strip_prefix('#')) {
+                    QueryTerm::Tag(tag.to_string())
+                } else if let Some(path) = lower.strip_prefix("path:") {
+                    QueryTerm::Path(path.trim_end_matches('/').to_string())
+                } else if let Some(property) = lower.strip_prefix("prop:") {
+                    match property.split_once('=') {
+                        Some((key, value)) => QueryTerm::Property(key.to_string(), Some(value.to_string())),
+                        None => QueryTerm::Property(property.to_string(), None),
+                    }
+                } else if let Some((key, value)) = lower.split_once("::") {
+                    QueryTerm::Property(key.to_string(), Some(value.trim().to_string()))
+                } else if lower == "is:journal" {
+                    QueryTerm::Journal
+                } else if lower == "is:orphan" {
+                    QueryTerm::Orphan
+                } else {
+                    QueryTerm::Text(lower)
+                };
+                match &term {
+                    QueryTerm::Text(s) | QueryTerm::Tag(s) | QueryTerm::Path(s) | QueryTerm::Property(s, _) if s.is_empty() => None,
+                    _ => Some((negated, term)),
+                }
+            })
+            .collect();
+        Self { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, facts: &PageFacts) -> bool {
+        self.terms.iter().all(|(negated, term)| {
+            let matched = match term {
+                QueryTerm::Text(word) => facts.title.contains(word.as_str()) || facts.text.contains(word.as_str()),
+                QueryTerm::Tag(tag) => facts.tags.iter().any(|t| t == tag),
+                QueryTerm::Path(path) => facts.title == *path || facts.title.starts_with(&format!("{}/", path)),
+                QueryTerm::Property(key, value) => match (facts.properties.get(key), value) {
+                    (Some(actual), Some(value)) => actual.split(',').any(|v| v.trim() == value.as_str()),
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                },
+                QueryTerm::Journal => facts.is_journal,
+                QueryTerm::Orphan => facts.is_orphan(),
+            };
+            matched != *negated
+        })
+    }
+}
+
+/// Split a query on spaces, keeping double-quoted runs together
+fn split_terms(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+    terms
+}
+
+/// Which journal pages the graph shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalFilter {
+    #[default]
+    All,
+    /// Journal pages only
+    Only,
+    /// Everything but journal pages
+    Hide,
+}
+
+/// Which pages the graph view shows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphFilters {
+    /// Pages must match this query when set
+    pub include: String,
+    /// Pages matching this query are left out
+    pub exclude: String,
+    pub journals: JournalFilter,
+    /// Show pages without links
+    pub show_orphans: bool,
+    /// Fewest connections a page needs to be shown
+    pub min_links: usize,
+}
+
+impl Default for GraphFilters {
+    fn default() -> Self {
+        Self {
+            include: String::new(),
+            exclude: String::new(),
+            journals: JournalFilter::All,
+            show_orphans: true,
+            min_links: 0,
+        }
+    }
+}
+
+impl GraphFilters {
+    /// Ids of the pages that pass the filters
+    pub fn visible(&self, facts: &HashMap<String, PageFacts>) -> HashSet<String> {
+        let include = GraphQuery::parse(&self.include);
+        let exclude = GraphQuery::parse(&self.exclude);
+        facts.iter()
+            .filter(|(_, f)| match self.journals {
+                JournalFilter::All => true,
+                JournalFilter::Only => f.is_journal,
+                JournalFilter::Hide => !f.is_journal,
+            })
+            .filter(|(_, f)| self.show_orphans || !f.is_orphan())
+            .filter(|(_, f)| f.inbound + f.outbound >= self.min_links)
+            .filter(|(_, f)| include.is_empty() || include.matches(f))
+            .filter(|(_, f)| exclude.is_empty() || !exclude.matches(f))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+}
+
+/// Pages matching a query are drawn in a color
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorGroup {
+    pub query: String,
+    /// CSS color
+    pub color: String,
+}
+
+/// Colors offered for new groups
+pub const GROUP_COLORS: [&str; 8] = [
+    "#2962ff", "#00c853", "#ff6d00", "#d50000", "#6200ea", "#00b8d4", "#ffab00", "#c51162",
+];
+
+/// Color of the first group a page matches
+pub fn group_color<'a>(groups: &'a [(GraphQuery, String)], facts: &PageFacts) -> Option<&'a str> {
+    groups.iter().find(|(query, _)| !query.is_empty() && query.matches(facts)).map(|(_, color)| color.as_str())
+}
+
+/// What node size follows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeSize {
+    /// Links in either direction
+    #[default]
+    Links,
+    Inbound,
+    Outbound,
+    WordCount,
+}
+
+impl NodeSize {
+    pub const ALL: [NodeSize; 4] = [NodeSize::Links, NodeSize::Inbound, NodeSize::Outbound, NodeSize::WordCount];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NodeSize::Links => "Links",
+            NodeSize::Inbound => "Inbound links",
+            NodeSize::Outbound => "Outbound links",
+            NodeSize::WordCount => "Word count",
+        }
+    }
+
+    /// Node radius for a page
+    pub fn radius(&self, facts: &PageFacts) -> f64 {
+        let value = match self {
+            NodeSize::Links => facts.inbound + facts.outbound,
+            NodeSize::Inbound => facts.inbound,
+            NodeSize::Outbound => facts.outbound,
+            // About one link's worth of size per 25 words
+            NodeSize::WordCount => facts.word_count / 25,
+        };
+        super::node_radius(value)
+    }
+}
+
+/// Everything the graph view's filter panel controls
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphSettings {
+    pub filters: GraphFilters,
+    /// Checked in order; the first match colors a node
+    pub groups: Vec<ColorGroup>,
+    /// Color the nodes no group matches by detected community
+    pub color_by_community: bool,
+    pub node_size: NodeSize,
+}
+
+impl GraphSettings {
+    /// Parsed group queries with their colors
+    pub fn group_queries(&self) -> Vec<(GraphQuery, String)> {
+        self.groups.iter().map(|g| (GraphQuery::parse(&g.query), g.color.clone())).collect()
+    }
+}
+
+/// Named graph settings saved for later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphPreset {
+    pub name: String,
+    pub settings: GraphSettings,
+}
+
+/// Save `settings` under `name`, replacing a preset with the same name
+pub fn save_preset(presets: &mut Vec<GraphPreset>, name: &str, settings: &GraphSettings) {
+    let name = name.trim();
+    if name.is_empty() {
+        return;
+    }
+    let preset = GraphPreset { name: name.to_string(), settings: settings.clone() };
+    match presets.iter_mut().find(|p| p.name.eq_ignore_ascii_case(name)) {
+        Some(existing) => *existing = preset,
+        None => presets.push(preset),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AppState;
+
+    /// Facts for a page holding `outline`
+    fn facts(outline: &str) -> PageFacts {
+        let mut state = AppState::default();
+        let id = state.create_page("Notes");
+        state.insert_outline(&id, outline);
+        let mut graph = KnowledgeGraph::default();
+        graph.build_from_state(&state.pages, &state.blocks, None);
+        page_facts(&state.pages, &state.blocks, &graph).remove(&id).unwrap()
+    }
+
+    fn matches(query: &str, outline: &str) -> bool {
+        GraphQuery::parse(query).matches(&facts(outline))
+    }
+
+    #[test]
+    fn tags_match_whole_tags_only() {
+        assert!(matches("tag:rust", "- learning #rust"));
+        assert!(matches("#rust", "- learning #[[Rust]]"));
+        assert!(!matches("tag:rust", "- learning #rustacean"));
+        assert!(!matches("tag:rust", "- `#rust` in code"));
+        assert!(matches("tag:rust", "- learning\n  - nested #rust"));
+    }
+
+    #[test]
+    fn block_tags_property_counts_as_tags() {
+        assert!(matches("tag:draft", "- tags:: draft, [[Ideas]]\n  body"));
+        assert!(matches("tag:ideas", "- tags:: draft, [[Ideas]]\n  body"));
+    }
+
+    #[test]
+    fn properties_include_block_properties() {
+        assert!(matches("prop:status", "- task\n  status:: done"));
+        assert!(matches("prop:status=done", "- task\n  status:: Done"));
+        assert!(matches("status::done", "- task\n  status:: done"));
+        assert!(!matches("prop:status=open", "- task\n  status:: done"));
+        assert!(matches("-prop:owner", "- task\n  status:: done"));
+    }
+
+    #[test]
+    fn repeated_block_properties_match_any_value() {
+        let outline = "- a\n  status:: done\n- b\n  status:: open";
+        assert!(matches("prop:status=done", outline));
+        assert!(matches("prop:status=open", outline));
+    }
+}
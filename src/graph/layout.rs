@@ -0,0 +1,720 @@
+// Force-directed graph layout
+// Barnes-Hut repulsion, link springs, centering and collision, cooled by alpha
+
+use std::collections::{HashMap, HashSet};
+use super::KnowledgeGraph;
+
+/// Quadtree cells deeper than this hold coincident nodes together
+const MAX_TREE_DEPTH: usize = 24;
+/// Ticks to settle a layout in one go
+pub const SETTLE_TICKS: usize = 300;
+/// Delay between animated simulation frames
+pub const FRAME_MS: u32 = 16;
+/// Time the simulation may take per frame before yielding to the UI
+pub const FRAME_BUDGET_MS: f64 = 8.0;
+
+/// Node position and velocity in layout space
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodePosition {
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+}
+
+/// Graph forces configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphForces {
+    /// Strength of the many-body repulsion between nodes
+    pub repulsion: f64,
+    /// Stiffness of the springs along links, scaled down for busy nodes
+    pub attraction: f64,
+    /// Rest length of a link
+    pub link_distance: f64,
+    /// Pull of every node toward the center
+    pub center: f64,
+    /// Share of velocity lost each tick
+    pub damping: f64,
+    /// How hard overlapping nodes are pushed apart, from 0 to 1
+    pub collision: f64,
+    /// Barnes-Hut accuracy: cells smaller than `theta` times their distance
+    /// act as one body. Lower is more exact and slower.
+    pub theta: f64,
+    /// How fast the simulation cools down each tick
+    pub alpha_decay: f64,
+    /// Alpha below which the layout counts as settled
+    pub alpha_min: f64,
+}
+
+impl Default for GraphForces {
+    fn default() -> Self {
+        Self {
+            repulsion: 30.0,
+            attraction: 1.0,
+            link_distance: 60.0,
+            center: 0.05,
+            damping: 0.4,
+            collision: 0.7,
+            theta: 0.9,
+            alpha_decay: 0.0228,
+            alpha_min: 0.001,
+        }
+    }
+}
+
+/// Radius a node is drawn (and collides) with, growing with its connections
+pub fn node_radius(link_count: usize) -> f64 {
+    8.0 + (link_count as f64).sqrt() * 2.5
+}
+
+/// Force-directed graph layout calculator. Positions survive `sync`, so a
+/// changed graph continues from the previous layout instead of starting over.
+#[derive(Debug, Clone)]
+pub struct GraphLayout {
+    pub forces: GraphForces,
+    /// Simulation temperature: 1 when fresh, cooling toward `alpha_target`
+    pub alpha: f64,
+    /// Temperature the simulation settles at; kept above zero while dragging
+    alpha_target: f64,
+    /// Nodes held in place by the user
+    pinned: HashMap<String, (f64, f64)>,
+    /// Node being dragged and where the pointer holds it
+    dragging: Option<(String, (f64, f64))>,
+    ids: Vec<String>,
+    index: HashMap<String, usize>,
+    bodies: Vec<NodePosition>,
+    radii: Vec<f64>,
+    /// Links as (source, target, spring strength, source share of the pull)
+    links: Vec<(usize, usize, f64, f64)>,
+    center: (f64, f64),
+}
+
+impl Default for GraphLayout {
+    fn default() -> Self {
+        Self::with_forces(GraphForces::default())
+    }
+}
+
+impl GraphLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_forces(forces: GraphForces) -> Self {
+        Self {
+            forces,
+            alpha: 1.0,
+            alpha_target: 0.0,
+            pinned: HashMap::new(),
+            dragging: None,
+            ids: Vec::new(),
+            index: HashMap::new(),
+            bodies: Vec::new(),
+            radii: Vec::new(),
+            links: Vec::new(),
+            center: (0.0, 0.0),
+        }
+    }
+
+    /// Calculate a settled layout for the graph in one go
+    pub fn calculate_layout(&mut self, graph: &KnowledgeGraph, width: f64, height: f64) -> HashMap<String, (f64, f64)> {
+        self.sync(graph, width, height);
+        self.run(SETTLE_TICKS);
+        self.positions()
+    }
+
+    /// Match the layout to the graph: keep known nodes where they are, place
+    /// new ones next to a linked neighbor, drop removed ones, and reheat
+    /// the simulation if anything changed
+    pub fn sync(&mut self, graph: &KnowledgeGraph, width: f64, height: f64) {
+        self.center = (width / 2.0, height / 2.0);
+        let fresh = self.bodies.is_empty();
+
+        let mut ids: Vec<String> = graph.nodes.keys().cloned().collect();
+        ids.sort();
+        let changed = ids != self.ids;
+
+        let mut bodies: Vec<Option<NodePosition>> = ids.iter()
+            .map(|id| self.index.get(id).map(|&i| self.bodies[i].clone()))
+            .collect();
+        let index: HashMap<String, usize> = ids.iter().cloned().enumerate().map(|(i, id)| (id, i)).collect();
+
+        // New nodes start beside a placed neighbor, or on a spiral around the center
+        let golden_angle = std::f64::consts::PI * (3.0 - 5f64.sqrt());
+        for i in 0..ids.len() {
+            if bodies[i].is_some() {
+                continue;
+            }
+            if let Some(&(x, y)) = self.pinned.get(&ids[i]) {
+                bodies[i] = Some(NodePosition { x, y, vx: 0.0, vy: 0.0 });
+                continue;
+            }
+            let angle = i as f64 * golden_angle;
+            let neighbor = graph.adjacency.get(&ids[i])
+                .and_then(|n| n.iter().filter_map(|id| index.get(id)).find_map(|&j| bodies[j].clone()));
+            let (x, y) = match neighbor {
+                Some(n) => (n.x + 10.0 * angle.cos(), n.y + 10.0 * angle.sin()),
+                None => {
+                    let radius = 10.0 * (0.5 + i as f64).sqrt();
+                    (self.center.0 + radius * angle.cos(), self.center.1 + radius * angle.sin())
+                }
+            };
+            bodies[i] = Some(NodePosition { x, y, vx: 0.0, vy: 0.0 });
+        }
+
+        // One spring per linked pair, whatever the direction or kind of link;
+        // springs are weaker on nodes with many links so hubs do not collapse
+        let mut degree = vec![0usize; ids.len()];
+        let mut pairs = Vec::with_capacity(graph.edges.len());
+        let mut seen = HashSet::new();
+        for edge in &graph.edges {
+            if let (Some(&s), Some(&t)) = (index.get(&edge.source), index.get(&edge.target)) {
+                if s != t && seen.insert((s.min(t), s.max(t))) {
+                    degree[s] += 1;
+                    degree[t] += 1;
+                    pairs.push((s, t));
+                }
+            }
+        }
+        self.links = pairs.into_iter()
+            .map(|(s, t)| {
+                let strength = 1.0 / degree[s].min(degree[t]) as f64;
+                let bias = degree[s] as f64 / (degree[s] + degree[t]) as f64;
+                (s, t, strength, bias)
+            })
+            .collect();
+
+        self.radii = ids.iter()
+            .map(|id| node_radius(graph.nodes.get(id).map(|n| n.link_count).unwrap_or(0)))
+            .collect();
+        self.bodies = bodies.into_iter().flatten().collect();
+        self.ids = ids;
+        self.index = index;
+
+        if fresh {
+            self.alpha = 1.0;
+        } else if changed {
+            self.alpha = self.alpha.max(0.3);
+        }
+    }
+
+    /// Heat the simulation back up, e.g. after the forces changed
+    pub fn reheat(&mut self, alpha: f64) {
+        self.alpha = self.alpha.max(alpha);
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.alpha < self.forces.alpha_min
+    }
+
+    /// Advance up to `ticks` steps; returns whether the layout is still moving
+    pub fn run(&mut self, ticks: usize) -> bool {
+        for _ in 0..ticks {
+            if self.is_settled() {
+                return false;
+            }
+            self.tick();
+        }
+        !self.is_settled()
+    }
+
+    /// Advance until settled or until `budget_ms` has passed on `now_ms`, but
+    /// at least one step; returns whether the layout is still moving
+    pub fn run_for(&mut self, budget_ms: f64, now_ms: impl Fn() -> f64) -> bool {
+        let started = now_ms();
+        let mut moving = !self.is_settled();
+        while moving {
+            moving = self.run(1);
+            if now_ms() - started >= budget_ms {
+                break;
+            }
+        }
+        moving
+    }
+
+    /// One simulation step
+    pub fn tick(&mut self) {
+        self.alpha += (self.alpha_target - self.alpha) * self.forces.alpha_decay;
+        let alpha = self.alpha;
+        if self.bodies.is_empty() {
+            return;
+        }
+
+        self.apply_links(alpha);
+        let tree = QuadTree::build(&self.bodies, &self.radii);
+        self.apply_repulsion(&tree, alpha);
+        self.apply_centering(alpha);
+        self.apply_collisions(&tree);
+
+        let keep = 1.0 - self.forces.damping;
+        for body in &mut self.bodies {
+            body.vx *= keep;
+            body.vy *= keep;
+            body.x += body.vx;
+            body.y += body.vy;
+        }
+
+        // Pinned and dragged nodes stay put; the rest of the graph reacts to them
+        let fixed = self.pinned.iter().map(|(id, &pos)| (id, pos))
+            .chain(self.dragging.iter().map(|(id, pos)| (id, *pos)));
+        for (id, (x, y)) in fixed {
+            if let Some(&i) = self.index.get(id) {
+                self.bodies[i] = NodePosition { x, y, vx: 0.0, vy: 0.0 };
+            }
+        }
+    }
+
+    /// Start dragging a node; the simulation keeps running until the drag ends
+    pub fn start_drag(&mut self, id: &str) {
+        if let Some(pos) = self.position(id) {
+            self.dragging = Some((id.to_string(), pos));
+            self.alpha_target = 0.3;
+            self.reheat(0.3);
+        }
+    }
+
+    pub fn drag_to(&mut self, x: f64, y: f64) {
+        if let Some((id, pos)) = self.dragging.as_mut() {
+            *pos = (x, y);
+            if let Some(&i) = self.index.get(id.as_str()) {
+                self.bodies[i] = NodePosition { x, y, vx: 0.0, vy: 0.0 };
+            }
+        }
+    }
+
+    /// Let go of the dragged node, pinning it where it was dropped if asked;
+    /// returns the dragged node
+    pub fn end_drag(&mut self, pin: bool) -> Option<String> {
+        let (id, pos) = self.dragging.take()?;
+        self.alpha_target = 0.0;
+        if pin {
+            self.pinned.insert(id.clone(), pos);
+        } else {
+            self.pinned.remove(&id);
+        }
+        Some(id)
+    }
+
+    pub fn dragging(&self) -> Option<&str> {
+        self.dragging.as_ref().map(|(id, _)| id.as_str())
+    }
+
+    /// Hold a node at its current position
+    pub fn pin(&mut self, id: &str) {
+        if let Some(pos) = self.position(id) {
+            self.pinned.insert(id.to_string(), pos);
+        }
+    }
+
+    pub fn unpin(&mut self, id: &str) {
+        if self.pinned.remove(id).is_some() {
+            self.reheat(0.1);
+        }
+    }
+
+    pub fn is_pinned(&self, id: &str) -> bool {
+        self.pinned.contains_key(id)
+    }
+
+    pub fn pinned(&self) -> &HashMap<String, (f64, f64)> {
+        &self.pinned
+    }
+
+    /// Replace the pinned positions, e.g. with saved ones
+    pub fn set_pinned(&mut self, pinned: HashMap<String, (f64, f64)>) {
+        for (id, &(x, y)) in &pinned {
+            if let Some(&i) = self.index.get(id) {
+                self.bodies[i] = NodePosition { x, y, vx: 0.0, vy: 0.0 };
+            }
+        }
+        self.pinned = pinned;
+    }
+
+    pub fn position(&self, id: &str) -> Option<(f64, f64)> {
+        self.index.get(id).map(|&i| (self.bodies[i].x, self.bodies[i].y))
+    }
+
+    pub fn positions(&self) -> HashMap<String, (f64, f64)> {
+        self.ids.iter().cloned().zip(self.bodies.iter().map(|b| (b.x, b.y))).collect()
+    }
+
+    /// Smallest box holding every node with its radius, as (min x, min y, max x, max y)
+    pub fn bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        if self.bodies.is_empty() {
+            return None;
+        }
+        let mut bounds = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (body, r) in self.bodies.iter().zip(&self.radii) {
+            bounds.0 = bounds.0.min(body.x - r);
+            bounds.1 = bounds.1.min(body.y - r);
+            bounds.2 = bounds.2.max(body.x + r);
+            bounds.3 = bounds.3.max(body.y + r);
+        }
+        Some(bounds)
+    }
+
+    /// Pull linked nodes toward `link_distance` apart
+    fn apply_links(&mut self, alpha: f64) {
+        let (attraction, distance) = (self.forces.attraction, self.forces.link_distance);
+        for &(s, t, strength, bias) in &self.links {
+            let (a, b) = (&self.bodies[s], &self.bodies[t]);
+            let mut dx = b.x + b.vx - a.x - a.vx;
+            let mut dy = b.y + b.vy - a.y - a.vy;
+            if dx == 0.0 && dy == 0.0 {
+                dx = jiggle(s);
+                dy = jiggle(t);
+            }
+            let length = (dx * dx + dy * dy).sqrt();
+            let k = (length - distance) / length * alpha * strength * attraction;
+            let (dx, dy) = (dx * k, dy * k);
+            self.bodies[t].vx -= dx * bias;
+            self.bodies[t].vy -= dy * bias;
+            self.bodies[s].vx += dx * (1.0 - bias);
+            self.bodies[s].vy += dy * (1.0 - bias);
+        }
+    }
+
+    /// Barnes-Hut many-body repulsion
+    fn apply_repulsion(&mut self, tree: &QuadTree, alpha: f64) {
+        let strength = self.forces.repulsion * alpha;
+        let theta2 = self.forces.theta * self.forces.theta;
+        let mut stack = Vec::with_capacity(64);
+
+        for i in 0..self.bodies.len() {
+            let (x, y) = (self.bodies[i].x, self.bodies[i].y);
+            let (mut fx, mut fy) = (0.0, 0.0);
+            stack.clear();
+            stack.push(0usize);
+
+            while let Some(q) = stack.pop() {
+                let cell = &tree.cells[q];
+                if cell.mass == 0.0 {
+                    continue;
+                }
+                let mut dx = cell.cx - x;
+                let mut dy = cell.cy - y;
+                let mut d2 = dx * dx + dy * dy;
+
+                match cell.children {
+                    // Far enough away: the whole cell acts as one body
+                    Some(children) if cell.size * cell.size >= theta2 * d2 => stack.extend(children),
+                    _ => {
+                        let mut mass = cell.mass;
+                        if cell.children.is_none() && cell.body == Some(i) {
+                            // Leaf holding this node (and maybe coincident ones)
+                            mass -= 1.0;
+                            if mass == 0.0 {
+                                continue;
+                            }
+                        }
+                        if d2 == 0.0 {
+                            dx = jiggle(i);
+                            dy = jiggle(i + q);
+                            d2 = dx * dx + dy * dy;
+                        }
+                        // Very close nodes are left to the collision force
+                        let d2 = d2.max(1.0);
+                        fx -= dx * strength * mass / d2;
+                        fy -= dy * strength * mass / d2;
+                    }
+                }
+            }
+
+            self.bodies[i].vx += fx;
+            self.bodies[i].vy += fy;
+        }
+    }
+
+    /// Gravity toward the middle of the canvas
+    fn apply_centering(&mut self, alpha: f64) {
+        let k = self.forces.center * alpha;
+        let (cx, cy) = self.center;
+        for body in &mut self.bodies {
+            body.vx += (cx - body.x) * k;
+            body.vy += (cy - body.y) * k;
+        }
+    }
+
+    /// Push apart nodes whose circles overlap, using the quadtree to find neighbors
+    fn apply_collisions(&mut self, tree: &QuadTree) {
+        let strength = self.forces.collision;
+        if strength <= 0.0 {
+            return;
+        }
+        let mut stack = Vec::with_capacity(64);
+
+        for i in 0..self.bodies.len() {
+            let ri = self.radii[i];
+            let xi = self.bodies[i].x + self.bodies[i].vx;
+            let yi = self.bodies[i].y + self.bodies[i].vy;
+            stack.clear();
+            stack.push(0usize);
+
+            while let Some(q) = stack.pop() {
+                let cell = &tree.cells[q];
+                let reach = ri + cell.max_radius;
+                // Skip cells that cannot contain an overlapping node
+                if xi + reach < cell.x0 || xi - reach > cell.x0 + cell.size
+                    || yi + reach < cell.y0 || yi - reach > cell.y0 + cell.size {
+                    continue;
+                }
+                if let Some(children) = cell.children {
+                    stack.extend(children);
+                    continue;
+                }
+                // Each pair once
+                let j = match cell.body {
+                    Some(j) if j > i => j,
+                    _ => continue,
+                };
+                let rj = self.radii[j];
+                let mut dx = xi - self.bodies[j].x - self.bodies[j].vx;
+                let mut dy = yi - self.bodies[j].y - self.bodies[j].vy;
+                let mut d2 = dx * dx + dy * dy;
+                let r = ri + rj;
+                if d2 >= r * r {
+                    continue;
+                }
+                if d2 == 0.0 {
+                    dx = jiggle(i);
+                    dy = jiggle(j);
+                    d2 = dx * dx + dy * dy;
+                }
+                let d = d2.sqrt();
+                let push = (r - d) / d * strength;
+                // Larger nodes move less
+                let share = rj * rj / (ri * ri + rj * rj);
+                self.bodies[i].vx += dx * push * share;
+                self.bodies[i].vy += dy * push * share;
+                self.bodies[j].vx -= dx * push * (1.0 - share);
+                self.bodies[j].vy -= dy * push * (1.0 - share);
+            }
+        }
+    }
+}
+
+/// Tiny deterministic offset to separate nodes on the same spot
+fn jiggle(seed: usize) -> f64 {
+    ((seed as f64 * 12.9898).sin() * 43758.5453).fract() * 1e-3 + 1e-6
+}
+
+/// A square region of the layout with the total mass and center of mass
+/// of the nodes inside it
+#[derive(Debug, Clone)]
+struct Cell {
+    x0: f64,
+    y0: f64,
+    size: f64,
+    mass: f64,
+    cx: f64,
+    cy: f64,
+    /// Largest node radius inside, for collision queries
+    max_radius: f64,
+    children: Option<[usize; 4]>,
+    /// Node held by a leaf; coincident nodes past `MAX_TREE_DEPTH` share a leaf
+    body: Option<usize>,
+}
+
+impl Cell {
+    fn new(x0: f64, y0: f64, size: f64) -> Self {
+        Self { x0, y0, size, mass: 0.0, cx: 0.0, cy: 0.0, max_radius: 0.0, children: None, body: None }
+    }
+}
+
+/// Barnes-Hut quadtree over node positions, stored as a flat arena
+struct QuadTree {
+    cells: Vec<Cell>,
+}
+
+impl QuadTree {
+    fn build(bodies: &[NodePosition], radii: &[f64]) -> Self {
+        let (mut x0, mut y0, mut x1, mut y1) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for b in bodies {
+            x0 = x0.min(b.x);
+            y0 = y0.min(b.y);
+            x1 = x1.max(b.x);
+            y1 = y1.max(b.y);
+        }
+        let size = (x1 - x0).max(y1 - y0).max(1.0) * 1.0001;
+
+        let mut tree = Self { cells: Vec::with_capacity(bodies.len() * 2 + 1) };
+        tree.cells.push(Cell::new(x0, y0, size));
+        for i in 0..bodies.len() {
+            tree.insert(bodies, radii, i);
+        }
+        tree
+    }
+
+    fn insert(&mut self, bodies: &[NodePosition], radii: &[f64], i: usize) {
+        let (x, y) = (bodies[i].x, bodies[i].y);
+        let mut q = 0;
+        let mut depth = 0;
+        loop {
+            // Every cell on the way down gains the node's mass
+            let cell = &mut self.cells[q];
+            cell.cx = (cell.cx * cell.mass + x) / (cell.mass + 1.0);
+            cell.cy = (cell.cy * cell.mass + y) / (cell.mass + 1.0);
+            cell.mass += 1.0;
+            cell.max_radius = cell.max_radius.max(radii[i]);
+
+            match (cell.children, cell.body) {
+                (Some(children), _) => {
+                    q = children[self.quadrant(q, x, y)];
+                }
+                (None, None) => {
+                    self.cells[q].body = Some(i);
+                    return;
+                }
+                (None, Some(_)) if depth >= MAX_TREE_DEPTH => return,
+                (None, Some(other)) => {
+                    // Split the leaf and push its node down one level
+                    let children = self.subdivide(q);
+                    let (ox, oy) = (bodies[other].x, bodies[other].y);
+                    let child = children[self.quadrant(q, ox, oy)];
+                    let mass = self.cells[q].mass - 1.0;
+                    let leaf = &mut self.cells[child];
+                    leaf.body = Some(other);
+                    leaf.mass = mass;
+                    leaf.cx = ox;
+                    leaf.cy = oy;
+                    leaf.max_radius = radii[other];
+                    self.cells[q].body = None;
+                    q = children[self.quadrant(q, x, y)];
+                }
+            }
+            depth += 1;
+        }
+    }
+
+    fn subdivide(&mut self, q: usize) -> [usize; 4] {
+        let (x0, y0, half) = (self.cells[q].x0, self.cells[q].y0, self.cells[q].size / 2.0);
+        let first = self.cells.len();
+        self.cells.push(Cell::new(x0, y0, half));
+        self.cells.push(Cell::new(x0 + half, y0, half));
+        self.cells.push(Cell::new(x0, y0 + half, half));
+        self.cells.push(Cell::new(x0 + half, y0 + half, half));
+        let children = [first, first + 1, first + 2, first + 3];
+        self.cells[q].children = Some(children);
+        children
+    }
+
+    fn quadrant(&self, q: usize, x: f64, y: f64) -> usize {
+        let cell = &self.cells[q];
+        let half = cell.size / 2.0;
+        let right = x >= cell.x0 + half;
+        let bottom = y >= cell.y0 + half;
+        (right as usize) + 2 * (bottom as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell as Counter;
+    use crate::graph::tests::graph;
+
+    fn bodies(points: &[(f64, f64)]) -> Vec<NodePosition> {
+        points.iter().map(|&(x, y)| NodePosition { x, y, vx: 0.0, vy: 0.0 }).collect()
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn quadtree_cells_sum_mass_and_center() {
+        let points: Vec<(f64, f64)> = (0..200)
+            .map(|i| (jiggle(i) * 1e6, jiggle(i + 1000) * 1e6))
+            .collect();
+        let tree = QuadTree::build(&bodies(&points), &vec![1.0; points.len()]);
+
+        let root = &tree.cells[0];
+        assert_close(root.mass, 200.0);
+        assert_close(root.cx, points.iter().map(|p| p.0).sum::<f64>() / 200.0);
+        assert_close(root.cy, points.iter().map(|p| p.1).sum::<f64>() / 200.0);
+
+        for cell in &tree.cells {
+            match cell.children {
+                Some(children) => {
+                    let mass: f64 = children.iter().map(|&c| tree.cells[c].mass).sum();
+                    let cx: f64 = children.iter().map(|&c| tree.cells[c].cx * tree.cells[c].mass).sum();
+                    let cy: f64 = children.iter().map(|&c| tree.cells[c].cy * tree.cells[c].mass).sum();
+                    assert_close(cell.mass, mass);
+                    assert_close(cell.cx * cell.mass, cx);
+                    assert_close(cell.cy * cell.mass, cy);
+                }
+                None => {
+                    let expected = if cell.body.is_some() { 1.0 } else { 0.0 };
+                    assert_close(cell.mass, expected);
+                    if let Some(i) = cell.body {
+                        assert_close(cell.cx, points[i].0);
+                        assert_close(cell.cy, points[i].1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quadtree_keeps_coincident_nodes_in_one_leaf() {
+        let points = [(5.0, 5.0), (5.0, 5.0), (5.0, 5.0), (50.0, 50.0)];
+        let tree = QuadTree::build(&bodies(&points), &[1.0; 4]);
+
+        assert_close(tree.cells[0].mass, 4.0);
+        let leaf_mass: f64 = tree.cells.iter()
+            .filter(|c| c.children.is_none() && c.body.is_some())
+            .map(|c| c.mass)
+            .sum();
+        assert_close(leaf_mass, 4.0);
+    }
+
+    #[test]
+    fn sync_warm_starts_known_nodes() {
+        let mut layout = GraphLayout::new();
+        let before = layout.calculate_layout(&graph(3, &[(0, 1), (1, 2)]), 400.0, 400.0);
+        assert!(layout.is_settled());
+
+        layout.sync(&graph(4, &[(0, 1), (1, 2), (3, 0)]), 400.0, 400.0);
+        for id in ["n0", "n1", "n2"] {
+            assert_eq!(layout.position(id), before.get(id).copied());
+        }
+        // The new node starts next to its neighbor and the layout reheats gently
+        let (x, y) = layout.position("n3").unwrap();
+        let (nx, ny) = before["n0"];
+        assert!(((x - nx).powi(2) + (y - ny).powi(2)).sqrt() <= 10.0 + 1e-9);
+        assert_close(layout.alpha, 0.3);
+    }
+
+    #[test]
+    fn sync_drops_removed_nodes_without_reheating_unchanged_graphs() {
+        let mut layout = GraphLayout::new();
+        layout.calculate_layout(&graph(3, &[(0, 1), (1, 2)]), 400.0, 400.0);
+        let alpha = layout.alpha;
+
+        layout.sync(&graph(3, &[(0, 1), (1, 2)]), 400.0, 400.0);
+        assert_close(layout.alpha, alpha);
+
+        layout.sync(&graph(2, &[(0, 1)]), 400.0, 400.0);
+        assert_eq!(layout.positions().len(), 2);
+        assert!(layout.position("n2").is_none());
+    }
+
+    #[test]
+    fn run_for_stops_at_the_budget() {
+        let mut layout = GraphLayout::new();
+        layout.sync(&graph(10, &[(0, 1), (2, 3)]), 400.0, 400.0);
+
+        // Each clock read advances a millisecond
+        let clock = Counter::new(0.0);
+        let now = || {
+            clock.set(clock.get() + 1.0);
+            clock.get()
+        };
+        assert!(layout.run_for(4.0, now));
+        assert!(layout.alpha < 1.0);
+        assert!(clock.get() <= 6.0);
+
+        assert!(!layout.run_for(f64::INFINITY, || 0.0));
+        assert!(layout.is_settled());
+    }
+}
@@ -1,6 +1,18 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::store::{Page, Block};
+use crate::markdown::{parse_inline, split_properties, Inline};
+
+pub mod analytics;
+pub mod filter;
+pub mod layout;
+
+pub use analytics::{GraphInsights, LinkSuggestion};
+pub use filter::{page_facts, ColorGroup, GraphFilters, GraphPreset, GraphQuery, GraphSettings, JournalFilter, NodeSize, PageFacts};
+pub use layout::{node_radius, GraphForces, GraphLayout, NodePosition, FRAME_BUDGET_MS, FRAME_MS, SETTLE_TICKS};
 
 /// Represents a node in the knowledge graph
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,12 +38,51 @@ impl GraphNode {
     }
 }
 
-/// Represents an edge between nodes
+/// What made one page point at another
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum EdgeKind {
+    /// `[[Page]]`
+    #[default]
+    WikiLink,
+    /// `#tag`, `#[[tag]]` or a page tag naming a page
+    Tag,
+    /// `((block-id))` pointing into another page
+    BlockRef,
+    /// `{{embed [[Page]]}}` or `{{embed ((block-id))}}`
+    Embed,
+    /// A `key:: value` property naming a page
+    Property,
+}
+
+impl EdgeKind {
+    pub const ALL: [EdgeKind; 5] = [
+        EdgeKind::WikiLink,
+        EdgeKind::Tag,
+        EdgeKind::BlockRef,
+        EdgeKind::Embed,
+        EdgeKind::Property,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EdgeKind::WikiLink => "Links",
+            EdgeKind::Tag => "Tags",
+            EdgeKind::BlockRef => "Block refs",
+            EdgeKind::Embed => "Embeds",
+            EdgeKind::Property => "Properties",
+        }
+    }
+}
+
+/// A directed edge from the page holding a reference to the page it names.
+/// Repeated references of one kind add to `weight`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphEdge {
     pub source: String,
     pub target: String,
     pub weight: u32,
+    #[serde(default)]
+    pub kind: EdgeKind,
 }
 
 impl GraphEdge {
@@ -40,16 +91,33 @@ impl GraphEdge {
             source: source.to_string(),
             target: target.to_string(),
             weight: 1,
+            kind: EdgeKind::WikiLink,
         }
     }
+
+    pub fn with_kind(mut self, kind: EdgeKind) -> Self {
+        self.kind = kind;
+        self
+    }
 }
 
 /// The knowledge graph structure
 #[derive(Debug, Clone)]
 pub struct KnowledgeGraph {
     pub nodes: HashMap<String, GraphNode>,
+    /// One edge per source, target and kind
     pub edges: Vec<GraphEdge>,
+    /// Pages linked in either direction
     pub adjacency: HashMap<String, HashSet<String>>,
+    /// Pages each page links to
+    pub outgoing: HashMap<String, HashSet<String>>,
+    /// Pages linking to each page
+    pub incoming: HashMap<String, HashSet<String>>,
+    /// Position in `edges` of each (source, target, kind)
+    edge_index: HashMap<(String, String, EdgeKind), usize>,
+    /// Weight of all references from a page to another, by source then
+    /// target, so path searches look weights up without allocating
+    link_weights: HashMap<String, HashMap<String, u32>>,
 }
 
 impl Default for KnowledgeGraph {
@@ -58,54 +126,82 @@ impl Default for KnowledgeGraph {
             nodes: HashMap::new(),
             edges: Vec::new(),
             adjacency: HashMap::new(),
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+            edge_index: HashMap::new(),
+            link_weights: HashMap::new(),
         }
     }
 }
 
+/// Something a block or property refers to, before it is resolved to a page
+enum Reference {
+    /// A page by title
+    Page(String),
+    /// A block by id, standing for the page that holds it
+    Block(String),
+}
+
 impl KnowledgeGraph {
-    /// Build the graph from app state
+    /// Build the graph from app state. References are resolved to page ids
+    /// by title (case-insensitive) or, for block refs and embeds, to the page
+    /// holding the block; references to missing pages are left out.
     pub fn build_from_state(&mut self, pages: &HashMap<String, Page>, blocks: &HashMap<String, Block>, active_page_id: Option<&str>) {
-        self.nodes.clear();
-        self.edges.clear();
-        self.adjacency.clear();
+        *self = KnowledgeGraph::default();
 
-        // Track link counts
-        let mut link_counts: HashMap<String, usize> = HashMap::new();
+        let ids_by_title: HashMap<String, &str> = pages.values()
+            .map(|p| (p.title.to_lowercase(), p.id.as_str()))
+            .collect();
+
+        // Every block of each page, nested ones included
+        let mut page_blocks: HashMap<&str, Vec<&Block>> = HashMap::new();
+        let mut owners: HashMap<&str, &str> = HashMap::new();
+        for (page_id, page) in pages {
+            let mut stack: Vec<&String> = page.blocks.iter().rev().collect();
+            let mut walked = Vec::new();
+            while let Some(block_id) = stack.pop() {
+                if let Some(block) = blocks.get(block_id) {
+                    owners.insert(block.id.as_str(), page_id.as_str());
+                    stack.extend(block.children.iter().rev());
+                    walked.push(block);
+                }
+            }
+            page_blocks.insert(page_id.as_str(), walked);
+        }
 
-        // First pass: collect all wikilinks and build nodes
+        let embed_re = regex::Regex::new(r"\{\{embed\s+(?:\[\[([^\]]+)\]\]|\(\(([^)]+)\)\))\s*\}\}").unwrap();
         for (page_id, page) in pages {
-            // Create node
-            let node = GraphNode::from_page(page, Some(page_id) == active_page_id);
+            let node = GraphNode::from_page(page, Some(page_id.as_str()) == active_page_id);
             self.nodes.insert(page_id.clone(), node);
 
-            // Parse wikilinks in blocks
-            for block_id in &page.blocks {
-                if let Some(block) = blocks.get(block_id) {
-                    let links = Self::extract_wikilinks(&block.content);
-                    for link in links {
-                        // Update link count for target
-                        *link_counts.entry(link.clone()).or_insert(0) += 1;
-
-                        // Add edge
-                        if link != page_id {
-                            self.edges.push(GraphEdge::new(page_id, &link));
-                            self.adjacency.entry(page_id.clone())
-                                .or_insert_with(HashSet::new)
-                                .insert(link.clone());
-                            self.adjacency.entry(link.clone())
-                                .or_insert_with(HashSet::new)
-                                .insert(page_id.clone());
-                        }
+            let mut references: Vec<(EdgeKind, Reference)> = Vec::new();
+            references.extend(page.tags.iter().map(|t| (EdgeKind::Tag, Reference::Page(t.clone()))));
+            for (key, value) in &page.properties {
+                if key != "tags" && key != "title" && key != "icon" {
+                    references.extend(property_references(value).into_iter().map(|r| (EdgeKind::Property, r)));
+                }
+            }
+            for block in page_blocks.get(page_id.as_str()).into_iter().flatten() {
+                block_references(&block.content, &embed_re, &mut references);
+            }
+
+            for (kind, reference) in references {
+                let target = match &reference {
+                    Reference::Page(title) => ids_by_title.get(&title.trim().to_lowercase()).copied(),
+                    Reference::Block(id) => owners.get(id.trim()).copied(),
+                };
+                match target {
+                    Some(target) if target != page_id.as_str() => {
+                        self.add_edge(GraphEdge::new(page_id, target).with_kind(kind));
                     }
+                    _ => {}
                 }
             }
         }
 
-        // Second pass: update link counts on nodes
-        for (node_id, link_count) in link_counts {
-            if let Some(node) = self.nodes.get_mut(&node_id) {
-                node.link_count = *link_count;
-            }
+        // Connections per page
+        for (node_id, node) in self.nodes.iter_mut() {
+            node.link_count = self.adjacency.get(node_id).map(|n| n.len()).unwrap_or(0);
         }
     }
 
@@ -139,12 +235,9 @@ impl KnowledgeGraph {
         }
 
         // From blocks
-        let tag_re = regex::Regex::new(r"#([a-zA-Z0-9_-]+)").unwrap();
         for block in blocks.values() {
-            for cap in tag_re.captures_iter(&block.content) {
-                if let Some(tag) = cap.get(1) {
-                    tags.insert(format!("#{}", tag.as_str()));
-                }
+            for tag in crate::markdown::collect_tags(&crate::markdown::parse_inline(&block.content)) {
+                tags.insert(format!("#{}", tag));
             }
         }
 
@@ -154,14 +247,12 @@ impl KnowledgeGraph {
     }
 
     /// Get related pages for a given page
-    pub fn get_related_pages(&self, page_id: &str) -> Vec<(String, usize)> {
+    pub fn get_related_pages(&self, page_id: &str, direction: LinkDirection) -> Vec<(String, usize)> {
         let mut related: Vec<(String, usize)> = Vec::new();
 
-        if let Some(connected) = self.adjacency.get(page_id) {
-            for neighbor_id in connected {
-                let weight = self.calculate_relevance(page_id, neighbor_id);
-                related.push((neighbor_id.clone(), weight));
-            }
+        for neighbor_id in self.neighbors(page_id, direction) {
+            let weight = self.calculate_relevance(page_id, neighbor_id);
+            related.push((neighbor_id.clone(), weight));
         }
 
         related.sort_by(|a, b| b.1.cmp(&a.1));
@@ -195,7 +286,12 @@ impl KnowledgeGraph {
 
     /// Get graph statistics
     pub fn get_stats(&self) -> GraphStats {
-        let total_links = self.edges.len();
+        // Linked page pairs, however many kinds of reference join them
+        let total_links = self.outgoing.values().map(|t| t.len()).sum();
+        let mut links_by_kind: Vec<(EdgeKind, usize)> = EdgeKind::ALL.iter()
+            .map(|kind| (*kind, self.edges.iter().filter(|e| e.kind == *kind).count()))
+            .collect();
+        links_by_kind.retain(|(_, count)| *count > 0);
         let connected_nodes = self.nodes.values().filter(|n| n.link_count > 0).count();
         
         // Find isolated nodes
@@ -206,6 +302,7 @@ impl KnowledgeGraph {
         GraphStats {
             total_pages: self.nodes.len(),
             total_links,
+            links_by_kind,
             connected_pages: connected_nodes,
             isolated_pages: isolated_nodes,
         }
@@ -223,8 +320,9 @@ impl KnowledgeGraph {
         hubs.into_iter().take(limit).collect()
     }
 
-    /// Perform BFS to find shortest path between two pages
-    pub fn find_path(&self, start: &str, end: &str) -> Option<Vec<String>> {
+    /// Perform BFS to find the path with the fewest hops between two pages,
+    /// following links in `direction`
+    pub fn find_path(&self, start: &str, end: &str, direction: LinkDirection) -> Option<Vec<String>> {
         if !self.nodes.contains_key(start) || !self.nodes.contains_key(end) {
             return None;
         }
@@ -240,179 +338,516 @@ impl KnowledgeGraph {
                 return Some(path);
             }
 
-            if let Some(neighbors) = self.adjacency.get(&current) {
-                for neighbor in neighbors {
-                    if !visited.contains(neighbor) {
-                        visited.insert(neighbor.clone());
-                        let mut new_path = path.clone();
-                        new_path.push(neighbor.clone());
-                        queue.push_back((neighbor.clone(), new_path));
+            for neighbor in self.neighbors(&current, direction) {
+                if !visited.contains(neighbor) {
+                    visited.insert(neighbor.clone());
+                    let mut new_path = path.clone();
+                    new_path.push(neighbor.clone());
+                    queue.push_back((neighbor.clone(), new_path));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Pages `page_id` links to or is linked from, sorted by id
+    pub fn neighbors(&self, page_id: &str, direction: LinkDirection) -> Vec<&String> {
+        let map = match direction {
+            LinkDirection::Both => &self.adjacency,
+            LinkDirection::Outgoing => &self.outgoing,
+            LinkDirection::Incoming => &self.incoming,
+        };
+        let mut neighbors: Vec<&String> = map.get(page_id).into_iter().flatten().collect();
+        neighbors.sort();
+        neighbors
+    }
+
+    /// Pages linked from `page_id`
+    pub fn outbound_neighbors(&self, page_id: &str) -> Vec<&String> {
+        self.neighbors(page_id, LinkDirection::Outgoing)
+    }
+
+    /// Pages linking to `page_id`
+    pub fn inbound_neighbors(&self, page_id: &str) -> Vec<&String> {
+        self.neighbors(page_id, LinkDirection::Incoming)
+    }
+
+    /// Edges from `source` to `target`, one per kind of reference
+    pub fn edges_between(&self, source: &str, target: &str) -> Vec<&GraphEdge> {
+        EdgeKind::ALL.iter()
+            .filter_map(|kind| self.edge_index.get(&(source.to_string(), target.to_string(), *kind)))
+            .map(|&i| &self.edges[i])
+            .collect()
+    }
+
+    /// Weight of all references from `source` to `target`
+    pub fn link_weight(&self, source: &str, target: &str) -> u32 {
+        self.link_weights.get(source).and_then(|targets| targets.get(target)).copied().unwrap_or(0)
+    }
+
+    /// Cheapest path between two pages and its cost. A step costs the
+    /// inverse of the link weight, so often-linked pages are closer.
+    pub fn weighted_path(&self, start: &str, end: &str, direction: LinkDirection) -> Option<(Vec<String>, f64)> {
+        self.cheapest_path(start, end, direction, &HashSet::new(), &HashSet::new())
+    }
+
+    /// Up to `k` cheapest paths without repeated pages, cheapest first
+    /// (Yen's algorithm over `weighted_path` costs)
+    pub fn k_shortest_paths(&self, start: &str, end: &str, k: usize, direction: LinkDirection) -> Vec<(Vec<String>, f64)> {
+        let mut found: Vec<(Vec<String>, f64)> = Vec::new();
+        let mut candidates: Vec<(Vec<String>, f64)> = Vec::new();
+        if k == 0 {
+            return found;
+        }
+        match self.weighted_path(start, end, direction) {
+            Some(path) => found.push(path),
+            None => return found,
+        }
+
+        while found.len() < k {
+            let last = found[found.len() - 1].0.clone();
+            // Branch off the last path at each of its pages in turn
+            for i in 0..last.len() - 1 {
+                let root = &last[..=i];
+                let banned_next: HashSet<&str> = found.iter()
+                    .filter(|(path, _)| path.len() > i + 1 && path[..=i] == *root)
+                    .map(|(path, _)| path[i + 1].as_str())
+                    .collect();
+                let banned_pages: HashSet<String> = root[..i].iter().cloned().collect();
+
+                if let Some((spur, _)) = self.cheapest_path(&last[i], end, direction, &banned_pages, &banned_next) {
+                    let mut path = root[..i].to_vec();
+                    path.extend(spur);
+                    if !found.iter().chain(candidates.iter()).any(|(p, _)| *p == path) {
+                        let cost = self.path_cost(&path, direction);
+                        candidates.push((path, cost));
                     }
                 }
             }
+
+            // Cheapest candidate next, the one with fewer hops on a tie
+            let best = candidates.iter().enumerate()
+                .min_by(|(_, a), (_, b)| a.1.total_cmp(&b.1).then_with(|| a.0.len().cmp(&b.0.len())))
+                .map(|(i, _)| i);
+            match best {
+                Some(i) => found.push(candidates.swap_remove(i)),
+                None => break,
+            }
+        }
+
+        found
+    }
+
+    /// Cost of one step along links in `direction`
+    fn step_cost(&self, from: &str, to: &str, direction: LinkDirection) -> f64 {
+        let weight = match direction {
+            LinkDirection::Outgoing => self.link_weight(from, to),
+            LinkDirection::Incoming => self.link_weight(to, from),
+            LinkDirection::Both => self.link_weight(from, to) + self.link_weight(to, from),
+        };
+        1.0 / weight.max(1) as f64
+    }
+
+    fn path_cost(&self, path: &[String], direction: LinkDirection) -> f64 {
+        path.windows(2).map(|step| self.step_cost(&step[0], &step[1], direction)).sum()
+    }
+
+    /// Dijkstra from `start` to `end` avoiding some pages, and some first
+    /// steps out of `start`
+    fn cheapest_path(
+        &self,
+        start: &str,
+        end: &str,
+        direction: LinkDirection,
+        banned_pages: &HashSet<String>,
+        banned_next: &HashSet<&str>,
+    ) -> Option<(Vec<String>, f64)> {
+        if !self.nodes.contains_key(start) || !self.nodes.contains_key(end) {
+            return None;
+        }
+
+        let mut costs: HashMap<String, f64> = HashMap::new();
+        let mut previous: HashMap<String, String> = HashMap::new();
+        let mut frontier = BinaryHeap::new();
+        costs.insert(start.to_string(), 0.0);
+        frontier.push(Frontier { cost: 0.0, id: start.to_string() });
+
+        while let Some(Frontier { cost, id }) = frontier.pop() {
+            if id == end {
+                let mut path = vec![id];
+                while let Some(prev) = previous.get(&path[path.len() - 1]) {
+                    path.push(prev.clone());
+                }
+                path.reverse();
+                return Some((path, cost));
+            }
+            if cost > costs.get(&id).copied().unwrap_or(f64::INFINITY) {
+                continue;
+            }
+            for neighbor in self.neighbors(&id, direction) {
+                if banned_pages.contains(neighbor) || (id == start && banned_next.contains(neighbor.as_str())) {
+                    continue;
+                }
+                let next = cost + self.step_cost(&id, neighbor, direction);
+                if next < costs.get(neighbor).copied().unwrap_or(f64::INFINITY) {
+                    costs.insert(neighbor.clone(), next);
+                    previous.insert(neighbor.clone(), id.clone());
+                    frontier.push(Frontier { cost: next, id: neighbor.clone() });
+                }
+            }
         }
 
         None
     }
 }
 
-/// Graph statistics
-#[derive(Debug, Clone)]
-pub struct GraphStats {
-    pub total_pages: usize,
-    pub total_links: usize,
-    pub connected_pages: usize,
-    pub isolated_pages: usize,
+/// A page waiting in the Dijkstra queue; cheapest first
+#[derive(Debug, PartialEq)]
+struct Frontier {
+    cost: f64,
+    id: String,
 }
 
-/// Node positions for visualization
-#[derive(Debug, Clone)]
-pub struct NodePosition {
-    pub x: f64,
-    pub y: f64,
-    pub vx: f64,
-    pub vy: f64,
-}
+impl Eq for Frontier {}
 
-/// Force-directed graph layout calculator
-pub struct GraphLayout {
-    pub nodes: HashMap<String, NodePosition>,
-    forces: GraphForces,
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| other.id.cmp(&self.id))
+    }
 }
 
-impl GraphLayout {
-    pub fn new() -> Self {
-        Self {
-            nodes: HashMap::new(),
-            forces: GraphForces::default(),
-        }
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    /// Calculate layout for the graph
-    pub fn calculate_layout(&mut self, graph: &KnowledgeGraph, width: f64, height: f64) -> HashMap<String, (f64, f64)> {
-        let center_x = width / 2.0;
-        let center_y = height / 2.0;
+/// Which links a local graph follows from the center page
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkDirection {
+    #[default]
+    Both,
+    /// Pages linked from the center: outlinks
+    Outgoing,
+    /// Pages linking to the center: backlinks
+    Incoming,
+}
 
-        // Initialize positions in a circle
-        let nodes: Vec<_> = graph.nodes.keys().collect();
-        let radius = width.min(height) / 4.0;
-        
-        self.nodes.clear();
-        for (i, node_id) in nodes.iter().enumerate() {
-            let angle = 2.0 * std::f64::consts::PI * i as f64 / nodes.len() as f64;
-            self.nodes.insert(
-                node_id.clone(),
-                NodePosition {
-                    x: center_x + radius * angle.cos(),
-                    y: center_y + radius * angle.sin(),
-                    vx: 0.0,
-                    vy: 0.0,
-                }
-            );
-        }
+/// Prefix of tag node ids in a local graph
+pub const TAG_NODE_PREFIX: &str = "tag:";
 
-        // Run force simulation
-        for _ in 0..100 {
-            self.apply_forces(graph, width, height);
-        }
+/// Options for a local graph around one page
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalGraphOptions {
+    /// Hops from the center page
+    pub depth: usize,
+    pub include_tags: bool,
+    pub direction: LinkDirection,
+}
 
-        // Return final positions
-        self.nodes.iter()
-            .map(|(id, pos)| (id.clone(), (pos.x, pos.y)))
-            .collect()
+impl Default for LocalGraphOptions {
+    fn default() -> Self {
+        Self { depth: 1, include_tags: false, direction: LinkDirection::Both }
     }
+}
+
+/// What the graph was built from: page and block counts and the latest
+/// edit. Any added, removed or edited page or block changes it.
+type GraphRevision = (usize, usize, Option<DateTime<Utc>>);
 
-    fn apply_forces(&mut self, graph: &KnowledgeGraph, width: f64, height: f64) {
-        let repulsion = 5000.0;
-        let attraction = 0.01;
-        let damping = 0.85;
+/// The whole graph, rebuilt only when pages or blocks change, so views
+/// deriving local graphs from it don't rebuild on every render
+#[derive(Debug, Default)]
+pub struct GraphCache {
+    revision: Option<GraphRevision>,
+    graph: Rc<KnowledgeGraph>,
+}
 
-        // Reset velocities
-        for pos in self.nodes.values_mut() {
-            pos.vx *= damping;
-            pos.vy *= damping;
+impl GraphCache {
+    pub fn get(&mut self, pages: &HashMap<String, Page>, blocks: &HashMap<String, Block>) -> Rc<KnowledgeGraph> {
+        let latest = pages.values().map(|p| p.updated_at)
+            .chain(blocks.values().map(|b| b.updated_at))
+            .max();
+        let revision = (pages.len(), blocks.len(), latest);
+        if self.revision != Some(revision) {
+            let mut graph = KnowledgeGraph::default();
+            graph.build_from_state(pages, blocks, None);
+            self.graph = Rc::new(graph);
+            self.revision = Some(revision);
         }
+        self.graph.clone()
+    }
+}
 
-        // Repulsion between all nodes
-        let nodes: Vec<_> = self.nodes.keys().collect();
-        for (i, a) in nodes.iter().enumerate() {
-            for b in nodes.iter().skip(i + 1) {
-                let pos_a = self.nodes.get(a).unwrap();
-                let pos_b = self.nodes.get(b).unwrap();
+impl GraphNode {
+    pub fn is_tag(&self) -> bool {
+        self.id.starts_with(TAG_NODE_PREFIX)
+    }
+}
 
-                let dx = pos_b.x - pos_a.x;
-                let dy = pos_b.y - pos_a.y;
-                let dist_sq = dx * dx + dy * dy;
-                let dist = dist_sq.sqrt().max(1.0);
+impl KnowledgeGraph {
+    /// Hop distance from `center` to every page within `depth` hops,
+    /// following links in `direction`
+    pub fn neighborhood(&self, center: &str, depth: usize, direction: LinkDirection) -> HashMap<String, usize> {
+        let mut distances: HashMap<String, usize> = HashMap::new();
+        if !self.nodes.contains_key(center) {
+            return distances;
+        }
 
-                let force = repulsion / dist_sq;
-                let fx = (dx / dist) * force;
-                let fy = (dy / dist) * force;
+        let mut queue: VecDeque<String> = VecDeque::new();
+        distances.insert(center.to_string(), 0);
+        queue.push_back(center.to_string());
 
-                if let Some(pos) = self.nodes.get_mut(a) {
-                    pos.vx -= fx;
-                    pos.vy -= fy;
-                }
-                if let Some(pos) = self.nodes.get_mut(b) {
-                    pos.vx += fx;
-                    pos.vy += fy;
+        while let Some(current) = queue.pop_front() {
+            let distance = distances[&current];
+            if distance >= depth {
+                continue;
+            }
+            for neighbor in self.neighbors(&current, direction) {
+                if !distances.contains_key(neighbor) {
+                    distances.insert(neighbor.clone(), distance + 1);
+                    queue.push_back(neighbor.clone());
                 }
             }
         }
 
-        // Attraction along edges
-        for edge in &graph.edges {
-            if let (Some(pos_a), Some(pos_b)) = (
-                self.nodes.get(&edge.source),
-                self.nodes.get(&edge.target)
-            ) {
-                let dx = pos_b.x - pos_a.x;
-                let dy = pos_b.y - pos_a.y;
-                let dist = (dx * dx + dy * dy).sqrt().max(1.0);
-
-                let force = dist * attraction;
-                let fx = (dx / dist) * force;
-                let fy = (dy / dist) * force;
+        distances
+    }
 
-                if let Some(pos) = self.nodes.get_mut(&edge.source) {
-                    pos.vx += fx;
-                    pos.vy += fy;
-                }
-                if let Some(pos) = self.nodes.get_mut(&edge.target) {
-                    pos.vx -= fx;
-                    pos.vy -= fy;
+    /// The part of the graph within `options.depth` hops of `center`, with
+    /// optional tag nodes linked to the pages carrying them
+    pub fn local_graph(&self, center: &str, options: &LocalGraphOptions) -> KnowledgeGraph {
+        let distances = self.neighborhood(center, options.depth, options.direction);
+        let ids: HashSet<String> = distances.into_keys().collect();
+        let mut local = self.subgraph(&ids);
+
+        if options.include_tags {
+            let pages: Vec<(String, Vec<String>)> = local.nodes.values()
+                .map(|n| (n.id.clone(), n.tags.clone()))
+                .collect();
+            for (page_id, tags) in pages {
+                for tag in tags {
+                    let tag_id = format!("{}{}", TAG_NODE_PREFIX, tag.to_lowercase());
+                    local.nodes.entry(tag_id.clone()).or_insert_with(|| GraphNode {
+                        id: tag_id.clone(),
+                        title: format!("#{}", tag),
+                        icon: None,
+                        tags: Vec::new(),
+                        link_count: 0,
+                        is_active: false,
+                    });
+                    local.add_edge(GraphEdge::new(&page_id, &tag_id).with_kind(EdgeKind::Tag));
                 }
             }
         }
 
-        // Apply velocities and keep nodes in bounds
-        for pos in self.nodes.values_mut() {
-            pos.x += pos.vx;
-            pos.y += pos.vy;
+        for (node_id, node) in local.nodes.iter_mut() {
+            node.link_count = local.adjacency.get(node_id).map(|n| n.len()).unwrap_or(0);
+        }
+        local
+    }
+
+    /// The pages in `ids` and the links between them, with connections
+    /// counted within the subgraph
+    pub fn subgraph(&self, ids: &HashSet<String>) -> KnowledgeGraph {
+        let mut sub = KnowledgeGraph::default();
+        for id in ids {
+            if let Some(node) = self.nodes.get(id) {
+                sub.nodes.insert(id.clone(), node.clone());
+            }
+        }
+        for edge in &self.edges {
+            if ids.contains(&edge.source) && ids.contains(&edge.target) {
+                sub.add_edge(edge.clone());
+            }
+        }
+        for (node_id, node) in sub.nodes.iter_mut() {
+            node.link_count = sub.adjacency.get(node_id).map(|n| n.len()).unwrap_or(0);
+        }
+        sub
+    }
 
-            // Keep in bounds
-            pos.x = pos.x.clamp(50.0, width - 50.0);
-            pos.y = pos.y.clamp(50.0, height - 50.0);
+    /// Add an edge, or add its weight to the edge of the same kind already
+    /// between the two pages
+    fn add_edge(&mut self, edge: GraphEdge) {
+        *self.link_weights.entry(edge.source.clone()).or_default()
+            .entry(edge.target.clone()).or_default() += edge.weight;
+        let key = (edge.source.clone(), edge.target.clone(), edge.kind);
+        if let Some(&i) = self.edge_index.get(&key) {
+            self.edges[i].weight += edge.weight;
+            return;
         }
+        self.adjacency.entry(edge.source.clone()).or_default().insert(edge.target.clone());
+        self.adjacency.entry(edge.target.clone()).or_default().insert(edge.source.clone());
+        self.outgoing.entry(edge.source.clone()).or_default().insert(edge.target.clone());
+        self.incoming.entry(edge.target.clone()).or_default().insert(edge.source.clone());
+        self.edge_index.insert(key, self.edges.len());
+        self.edges.push(edge);
     }
 }
 
-/// Graph forces configuration
+/// Graph statistics
 #[derive(Debug, Clone)]
-struct GraphForces {
-    pub repulsion: f64,
-    pub attraction: f64,
-    pub center: f64,
-    pub damping: f64,
+pub struct GraphStats {
+    pub total_pages: usize,
+    pub total_links: usize,
+    /// Edges of each kind that occurs
+    pub links_by_kind: Vec<(EdgeKind, usize)>,
+    pub connected_pages: usize,
+    pub isolated_pages: usize,
 }
 
-impl Default for GraphForces {
-    fn default() -> Self {
-        Self {
-            repulsion: 5000.0,
-            attraction: 0.01,
-            center: 0.001,
-            damping: 0.85,
+/// References in one block: property values, embeds, then links, tags and
+/// block refs in the text
+fn block_references(content: &str, embed_re: &regex::Regex, references: &mut Vec<(EdgeKind, Reference)>) {
+    let (properties, body) = split_properties(content);
+    for (key, value) in properties {
+        let kind = if key == "tags" { EdgeKind::Tag } else { EdgeKind::Property };
+        references.extend(property_references(&value).into_iter().map(|r| (kind, r)));
+    }
+
+    // Embeds are taken out first so their link is not counted twice
+    let body = embed_re.replace_all(&body, |caps: &regex::Captures| {
+        match (caps.get(1), caps.get(2)) {
+            (Some(page), _) => references.push((EdgeKind::Embed, Reference::Page(page_link_target(page.as_str())))),
+            (_, Some(block)) => references.push((EdgeKind::Embed, Reference::Block(block.as_str().to_string()))),
+            _ => {}
+        }
+        ""
+    });
+    inline_references(&parse_inline(&body), references);
+}
+
+/// Pages a property value names: its links and tags, or else each
+/// comma-separated value
+fn property_references(value: &str) -> Vec<Reference> {
+    let mut found = Vec::new();
+    inline_references(&parse_inline(value), &mut found);
+    if found.is_empty() {
+        return value.split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| Reference::Page(v.to_string()))
+            .collect();
+    }
+    found.into_iter().map(|(_, r)| r).collect()
+}
+
+fn inline_references(nodes: &[Inline], references: &mut Vec<(EdgeKind, Reference)>) {
+    for node in nodes {
+        match node {
+            Inline::PageRef { page, .. } => references.push((EdgeKind::WikiLink, Reference::Page(page.clone()))),
+            Inline::Tag(tag) => references.push((EdgeKind::Tag, Reference::Page(tag.clone()))),
+            Inline::BlockRef(id) => references.push((EdgeKind::BlockRef, Reference::Block(id.clone()))),
+            Inline::Strong(children)
+            | Inline::Emphasis(children)
+            | Inline::Strikethrough(children)
+            | Inline::Highlight(children)
+            | Inline::Link { label: children, .. } => inline_references(children, references),
+            _ => {}
+        }
+    }
+}
+
+/// Page named by the inside of `[[...]]`, without an alias
+fn page_link_target(link: &str) -> String {
+    link.split('|').next().unwrap_or(link).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AppState;
+
+    /// Nodes `n0`..`n{count}` with the given links
+    pub(crate) fn graph(count: usize, links: &[(usize, usize)]) -> KnowledgeGraph {
+        let mut graph = KnowledgeGraph::default();
+        for i in 0..count {
+            let id = format!("n{}", i);
+            graph.nodes.insert(id.clone(), GraphNode {
+                id: id.clone(),
+                title: id,
+                icon: None,
+                tags: Vec::new(),
+                link_count: 0,
+                is_active: false,
+            });
+        }
+        for &(s, t) in links {
+            graph.add_edge(GraphEdge::new(&format!("n{}", s), &format!("n{}", t)));
         }
+        graph
+    }
+
+    #[test]
+    fn cache_rebuilds_only_after_edits() {
+        let mut state = AppState::default();
+        let a = state.create_page("A");
+        state.create_page("B");
+        let blocks = state.insert_outline(&a, "- see [[B]]");
+        let mut cache = GraphCache::default();
+
+        let first = cache.get(&state.pages, &state.blocks);
+        assert_eq!(first.edges.len(), 1);
+        assert!(Rc::ptr_eq(&first, &cache.get(&state.pages, &state.blocks)));
+
+        let block = state.blocks.get_mut(&blocks[0]).unwrap();
+        block.content = "no links".to_string();
+        block.updated_at = Utc::now() + chrono::Duration::seconds(1);
+        let rebuilt = cache.get(&state.pages, &state.blocks);
+        assert!(!Rc::ptr_eq(&first, &rebuilt));
+        assert!(rebuilt.edges.is_empty());
+    }
+
+    #[test]
+    fn link_weights_sum_every_kind() {
+        let mut graph = graph(2, &[(0, 1), (0, 1)]);
+        graph.add_edge(GraphEdge::new("n0", "n1").with_kind(EdgeKind::Embed));
+        assert_eq!(graph.link_weight("n0", "n1"), 3);
+        assert_eq!(graph.link_weight("n1", "n0"), 0);
+    }
+
+    #[test]
+    fn weighted_path_prefers_heavy_links_over_fewer_hops() {
+        // n0 -> n3 once, n0 -> n1 -> n3 three times each
+        let graph = graph(4, &[(0, 3), (0, 1), (0, 1), (0, 1), (1, 3), (1, 3), (1, 3)]);
+        let (path, cost) = graph.weighted_path("n0", "n3", LinkDirection::Outgoing).unwrap();
+        assert_eq!(path, ["n0", "n1", "n3"]);
+        assert!((cost - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weighted_path_follows_direction() {
+        let chain = graph(3, &[(0, 1), (1, 2)]);
+        assert!(chain.weighted_path("n2", "n0", LinkDirection::Outgoing).is_none());
+        assert_eq!(chain.weighted_path("n2", "n0", LinkDirection::Incoming).unwrap().0, ["n2", "n1", "n0"]);
+        assert!(chain.weighted_path("n0", "n2", LinkDirection::Incoming).is_none());
+
+        // Links both ways add up when direction is ignored
+        let mutual = graph(3, &[(0, 2), (0, 1), (1, 0), (1, 2), (2, 1), (2, 1)]);
+        assert_eq!(mutual.weighted_path("n0", "n2", LinkDirection::Outgoing).unwrap().0, ["n0", "n2"]);
+        let (path, cost) = mutual.weighted_path("n0", "n2", LinkDirection::Both).unwrap();
+        assert_eq!(path, ["n0", "n1", "n2"]);
+        assert!((cost - 5.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn k_shortest_paths_stops_at_every_simple_path() {
+        // n0 -> n3 directly, through n1, through n2, and through n1 then n2
+        let graph = graph(4, &[(0, 3), (0, 1), (1, 3), (0, 2), (2, 3), (1, 2)]);
+        let paths = graph.k_shortest_paths("n0", "n3", 10, LinkDirection::Outgoing);
+        let found: Vec<Vec<&str>> = paths.iter()
+            .map(|(path, _)| path.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(found, [
+            vec!["n0", "n3"],
+            vec!["n0", "n1", "n3"],
+            vec!["n0", "n2", "n3"],
+            vec!["n0", "n1", "n2", "n3"],
+        ]);
+        assert!(paths.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert!(graph.k_shortest_paths("n0", "n3", 0, LinkDirection::Outgoing).is_empty());
+        assert!(graph.k_shortest_paths("n3", "n0", 3, LinkDirection::Outgoing).is_empty());
     }
 }
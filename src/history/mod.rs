@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Visits kept in the history; older ones are dropped
+pub const HISTORY_LIMIT: usize = 200;
+
+/// What a visit opened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VisitTarget {
+    Page(String),
+    /// A block zoomed in
+    Block(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Visit {
+    pub target: VisitTarget,
+    pub at: DateTime<Utc>,
+}
+
+/// Back/forward stack of page and block visits, like a browser's
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NavHistory {
+    entries: Vec<Visit>,
+    /// Index of the current entry
+    cursor: usize,
+    /// Latest visit per target, newest first; survives back-stack truncation
+    #[serde(default)]
+    recent: Vec<Visit>,
+}
+
+impl NavHistory {
+    /// Record a visit. With `traversed` (the browser's back/forward moved to
+    /// `target`), revisiting the entry just behind or ahead of the cursor
+    /// moves the cursor instead, so browser and store history agree.
+    /// Otherwise the visit drops the forward entries and is pushed.
+    pub fn visit(&mut self, target: VisitTarget, at: DateTime<Utc>, traversed: bool) {
+        self.recent.retain(|v| v.target != target);
+        self.recent.insert(0, Visit { target: target.clone(), at });
+        self.recent.truncate(HISTORY_LIMIT);
+
+        if let Some(current) = self.entries.get_mut(self.cursor) {
+            if current.target == target {
+                current.at = at;
+                return;
+            }
+        }
+        if traversed {
+            if self.cursor > 0 && self.entries.get(self.cursor - 1).map(|v| &v.target) == Some(&target) {
+                self.cursor -= 1;
+                self.entries[self.cursor].at = at;
+                return;
+            }
+            if self.entries.get(self.cursor + 1).map(|v| &v.target) == Some(&target) {
+                self.cursor += 1;
+                self.entries[self.cursor].at = at;
+                return;
+            }
+        }
+
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.cursor + 1);
+        }
+        self.entries.push(Visit { target, at });
+        if self.entries.len() > HISTORY_LIMIT {
+            let excess = self.entries.len() - HISTORY_LIMIT;
+            self.entries.drain(..excess);
+        }
+        self.cursor = self.entries.len() - 1;
+    }
+
+    pub fn current(&self) -> Option<&Visit> {
+        self.entries.get(self.cursor)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.cursor + 1 < self.entries.len()
+    }
+
+    /// Step back; returns the visit to show
+    pub fn back(&mut self) -> Option<&Visit> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.cursor -= 1;
+        self.entries.get(self.cursor)
+    }
+
+    /// Step forward; returns the visit to show
+    pub fn forward(&mut self) -> Option<&Visit> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.cursor += 1;
+        self.entries.get(self.cursor)
+    }
+
+    /// Step back or forward to the nearest visit `exists` accepts, skipping
+    /// the others; the cursor stays put when there is none
+    pub fn step(&mut self, forward: bool, exists: impl Fn(&VisitTarget) -> bool) -> Option<&Visit> {
+        let start = self.cursor;
+        while if forward { self.can_go_forward() } else { self.can_go_back() } {
+            if forward {
+                self.cursor += 1;
+            } else {
+                self.cursor -= 1;
+            }
+            if exists(&self.entries[self.cursor].target) {
+                return self.entries.get(self.cursor);
+            }
+        }
+        self.cursor = start;
+        None
+    }
+
+    /// Pages by most recent visit, without repeats
+    pub fn recent_pages(&self, limit: usize) -> Vec<(String, DateTime<Utc>)> {
+        self.recent
+            .iter()
+            .filter_map(|v| match &v.target {
+                VisitTarget::Page(id) => Some((id.clone(), v.at)),
+                VisitTarget::Block(_) => None,
+            })
+            .take(limit)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(id: &str) -> VisitTarget {
+        VisitTarget::Page(id.to_string())
+    }
+
+    fn history(ids: &[&str]) -> NavHistory {
+        let mut history = NavHistory::default();
+        for id in ids {
+            history.visit(page(id), Utc::now(), false);
+        }
+        history
+    }
+
+    #[test]
+    fn links_to_the_previous_page_push() {
+        // a -> b, then a link on b back to a is a new entry
+        let mut history = history(&["a", "b"]);
+        history.visit(page("a"), Utc::now(), false);
+        assert_eq!(history.current().map(|v| &v.target), Some(&page("a")));
+        assert!(history.can_go_back());
+        assert!(!history.can_go_forward());
+        assert_eq!(history.back().map(|v| v.target.clone()), Some(page("b")));
+    }
+
+    #[test]
+    fn browser_traversal_moves_the_cursor() {
+        let mut history = history(&["a", "b", "c"]);
+        history.visit(page("b"), Utc::now(), true);
+        assert_eq!(history.current().map(|v| &v.target), Some(&page("b")));
+        assert!(history.can_go_forward());
+
+        history.visit(page("c"), Utc::now(), true);
+        assert_eq!(history.current().map(|v| &v.target), Some(&page("c")));
+        assert!(!history.can_go_forward());
+    }
+
+    #[test]
+    fn revisiting_the_current_entry_does_not_duplicate() {
+        let mut history = history(&["a", "b"]);
+        history.visit(page("b"), Utc::now(), false);
+        assert_eq!(history.back().map(|v| v.target.clone()), Some(page("a")));
+        assert!(!history.can_go_back());
+    }
+
+    #[test]
+    fn step_skips_missing_visits() {
+        let mut history = history(&["a", "gone", "c"]);
+        let visit = history.step(false, |t| *t != page("gone")).cloned();
+        assert_eq!(visit.map(|v| v.target), Some(page("a")));
+    }
+
+    #[test]
+    fn step_keeps_the_cursor_when_nothing_exists() {
+        let mut history = history(&["gone", "also-gone", "c"]);
+        assert!(history.step(false, |t| *t == page("c")).is_none());
+        assert_eq!(history.current().map(|v| &v.target), Some(&page("c")));
+        assert!(history.can_go_back());
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Default title format for journal pages
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Journal settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalConfig {
+    /// chrono format string used for journal page titles
+    pub date_format: String,
+    /// Outline applied to new journal pages, one block per `- ` line
+    pub template: String,
+    /// Create today's journal page on launch
+    pub auto_create: bool,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            template: String::new(),
+            auto_create: true,
+        }
+    }
+}
+
+impl JournalConfig {
+    /// Title of the journal page for a date, in the default format if the
+    /// configured one is invalid
+    pub fn title_for(&self, date: &NaiveDate) -> String {
+        crate::utils::get_date_title(date, &self.date_format)
+            .unwrap_or_else(|| date.format(DEFAULT_DATE_FORMAT).to_string())
+    }
+
+    /// Parse a journal page title back into its date
+    pub fn parse_title(&self, title: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(title.trim(), &self.date_format)
+            .or_else(|_| NaiveDate::parse_from_str(title.trim(), DEFAULT_DATE_FORMAT))
+            .ok()
+    }
+
+    /// Check that the format produces a title that parses back to the same date
+    pub fn is_valid_format(format: &str) -> bool {
+        let probe = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap_or_default();
+        crate::utils::get_date_title(&probe, format)
+            .and_then(|title| NaiveDate::parse_from_str(&title, format).ok())
+            .map_or(false, |d| d == probe)
+    }
+}
+
+/// Today's date in the local timezone
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+/// Current local date and time
+pub fn now() -> chrono::NaiveDateTime {
+    chrono::Local::now().naive_local()
+}
+
+/// Parse a `- item` outline into (depth, content) pairs.
+/// Two spaces or a tab per level; lines without a bullet continue the previous item.
+pub fn parse_outline(text: &str) -> Vec<(usize, String)> {
+    let mut items: Vec<(usize, String)> = Vec::new();
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent: usize = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 2 } else { 1 })
+            .sum();
+        let trimmed = line.trim_start();
+
+        match trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            Some(content) => items.push((indent / 2, content.to_string())),
+            None => match items.last_mut() {
+                Some((_, content)) => {
+                    content.push('\n');
+                    content.push_str(trimmed);
+                }
+                None => items.push((0, trimmed.to_string())),
+            },
+        }
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_formats_round_trip() {
+        assert!(JournalConfig::is_valid_format("%Y-%m-%d"));
+        assert!(JournalConfig::is_valid_format("%b %-d, %Y"));
+        assert!(!JournalConfig::is_valid_format("%m-%d"));
+    }
+
+    #[test]
+    fn broken_formats_are_rejected_without_panicking() {
+        assert!(!JournalConfig::is_valid_format("%"));
+        assert!(!JournalConfig::is_valid_format("%Y-%m-%"));
+        assert!(!JournalConfig::is_valid_format("%Y-%m-%d %H:%M"));
+        assert_eq!(crate::utils::get_date_title(&today(), "%"), None);
+    }
+
+    #[test]
+    fn invalid_config_falls_back_to_default_titles() {
+        let config = JournalConfig { date_format: "%Y %".to_string(), ..Default::default() };
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(config.title_for(&date), "2026-03-09");
+        assert_eq!(config.parse_title("2026-03-09"), Some(date));
+    }
+}
@@ -2,8 +2,19 @@
 // A knowledge management tool inspired by Logseq and Obsidian
 
 pub mod app;
+pub mod autocomplete;
 pub mod components;
 pub mod store;
 pub mod storage;
+pub mod tasks;
+pub mod templates;
 pub mod graph;
+pub mod history;
+pub mod journal;
+pub mod markdown;
+pub mod outline;
+pub mod reminders;
+pub mod right_sidebar;
+pub mod slash;
 pub mod utils;
+pub mod workspace;
@@ -2,11 +2,22 @@ use dioxus::prelude::*;
 use dioxus_web::Config;
 
 mod app;
+mod autocomplete;
 mod components;
 mod store;
 mod storage;
+mod tasks;
+mod templates;
 mod graph;
+mod history;
+mod journal;
+mod markdown;
+mod outline;
+mod reminders;
+mod right_sidebar;
+mod slash;
 mod utils;
+mod workspace;
 
 use crate::app::App;
 use crate::storage::StorageProvider;
@@ -0,0 +1,614 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::ops::Range;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, OffsetIter, Options, Parser, Tag};
+use super::inline::{escape_html, parse_inline, to_html as inline_to_html, Inline};
+
+/// A block-level markdown element
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockNode {
+    Paragraph(Vec<Inline>),
+    Heading { level: u8, content: Vec<Inline> },
+    /// `code` is verbatim, including its final newline
+    CodeBlock { lang: Option<String>, code: String },
+    /// A paragraph holding only `$$...$$` display math
+    Math(String),
+    Quote(Vec<BlockNode>),
+    /// Items of a `tight` list hold no blank lines and render without paragraphs
+    List { start: Option<u64>, tight: bool, items: Vec<ListItem> },
+    Table { alignments: Vec<ColumnAlign>, head: Vec<Vec<Inline>>, rows: Vec<Vec<Vec<Inline>>> },
+    FootnoteDefinition { label: String, content: Vec<BlockNode> },
+    Rule,
+}
+
+/// A list item; `checked` is set for GFM task list items
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem {
+    pub checked: Option<bool>,
+    pub content: Vec<BlockNode>,
+}
+
+/// Table column alignment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnAlign {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+impl ColumnAlign {
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            ColumnAlign::None | ColumnAlign::Left => "text-left",
+            ColumnAlign::Center => "text-center",
+            ColumnAlign::Right => "text-right",
+        }
+    }
+}
+
+impl From<Alignment> for ColumnAlign {
+    fn from(align: Alignment) -> Self {
+        match align {
+            Alignment::None => ColumnAlign::None,
+            Alignment::Left => ColumnAlign::Left,
+            Alignment::Center => ColumnAlign::Center,
+            Alignment::Right => ColumnAlign::Right,
+        }
+    }
+}
+
+/// Parse block content into block-level elements.
+/// Block structure comes from pulldown-cmark (CommonMark + GFM tables,
+/// task lists, strikethrough and footnotes); the source text of each leaf
+/// is handed to `parse_inline` so wikilinks, tags and block refs survive.
+pub fn parse_blocks(text: &str) -> Vec<BlockNode> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut builder = Builder {
+        source: text,
+        events: Parser::new_ext(text, options).into_offset_iter().peekable(),
+    };
+    builder.blocks()
+}
+
+struct Builder<'a> {
+    source: &'a str,
+    events: Peekable<OffsetIter<'a>>,
+}
+
+impl<'a> Builder<'a> {
+    /// Parse blocks until the end of the enclosing container
+    fn blocks(&mut self) -> Vec<BlockNode> {
+        self.container_blocks().0
+    }
+
+    /// Like `blocks`, also telling whether any paragraph was tagged as one.
+    /// pulldown-cmark leaves paragraph tags out of tight list items.
+    fn container_blocks(&mut self) -> (Vec<BlockNode>, bool) {
+        let mut nodes = Vec::new();
+        let mut paragraphs = false;
+
+        while let Some((event, range)) = self.events.peek().cloned() {
+            match event {
+                Event::End(_) => {
+                    self.events.next();
+                    break;
+                }
+                Event::Start(tag) if !is_inline_tag(&tag) => {
+                    paragraphs |= matches!(tag, Tag::Paragraph);
+                    self.events.next();
+                    if let Some(node) = self.container(tag, range) {
+                        nodes.push(node);
+                    }
+                }
+                Event::Rule => {
+                    self.events.next();
+                    nodes.push(BlockNode::Rule);
+                }
+                Event::Html(_) => {
+                    self.events.next();
+                    nodes.push(BlockNode::Paragraph(vec![Inline::Text(self.source[range].trim_end().to_string())]));
+                }
+                Event::TaskListMarker(_) => {
+                    self.events.next();
+                }
+                // Tight list items carry inline content without a paragraph
+                _ => {
+                    let content = self.inline_run();
+                    if !content.is_empty() {
+                        nodes.push(BlockNode::Paragraph(content));
+                    }
+                }
+            }
+        }
+
+        (nodes, paragraphs)
+    }
+
+    fn container(&mut self, tag: Tag<'a>, range: Range<usize>) -> Option<BlockNode> {
+        let node = match tag {
+            Tag::Paragraph => match self.leaf().as_slice() {
+                [Inline::DisplayMath(tex)] => BlockNode::Math(tex.clone()),
+                content => BlockNode::Paragraph(content.to_vec()),
+            },
+            Tag::Heading { level, .. } => BlockNode::Heading {
+                level: heading_level(level),
+                content: self.leaf(),
+            },
+            Tag::CodeBlock(kind) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .map(|l| l.to_lowercase()),
+                    CodeBlockKind::Indented => None,
+                };
+                BlockNode::CodeBlock { lang, code: self.raw_text() }
+            }
+            Tag::BlockQuote(_) => BlockNode::Quote(self.blocks()),
+            Tag::List(start) => {
+                let mut items = Vec::new();
+                let mut tight = true;
+                while let Some((Event::Start(Tag::Item), _)) = self.events.peek() {
+                    self.events.next();
+                    let checked = match self.events.peek() {
+                        Some((Event::TaskListMarker(checked), _)) => Some(*checked),
+                        _ => None,
+                    };
+                    let (content, paragraphs) = self.container_blocks();
+                    tight &= !paragraphs;
+                    items.push(ListItem { checked, content });
+                }
+                self.events.next();
+                BlockNode::List { start, tight, items }
+            }
+            Tag::Table(alignments) => self.table(alignments),
+            Tag::FootnoteDefinition(label) => BlockNode::FootnoteDefinition {
+                label: label.to_string(),
+                content: self.blocks(),
+            },
+            Tag::HtmlBlock => {
+                let html = self.raw_text();
+                BlockNode::Paragraph(vec![Inline::Text(html.trim_end().to_string())])
+            }
+            _ => {
+                // Unsupported containers (metadata, definition lists) keep their source
+                self.skip_container();
+                BlockNode::Paragraph(parse_inline(self.source[range].trim()))
+            }
+        };
+
+        Some(node)
+    }
+
+    fn table(&mut self, alignments: Vec<Alignment>) -> BlockNode {
+        let mut head = Vec::new();
+        let mut rows = Vec::new();
+
+        while let Some((event, _)) = self.events.next() {
+            match event {
+                Event::Start(Tag::TableHead) => head = self.table_row(),
+                Event::Start(Tag::TableRow) => rows.push(self.table_row()),
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+
+        BlockNode::Table {
+            alignments: alignments.into_iter().map(ColumnAlign::from).collect(),
+            head,
+            rows,
+        }
+    }
+
+    fn table_row(&mut self) -> Vec<Vec<Inline>> {
+        let mut cells = Vec::new();
+        while let Some((event, _)) = self.events.next() {
+            match event {
+                // GFM unescapes `\|` before inline parsing, even in code spans
+                Event::Start(Tag::TableCell) => {
+                    cells.push(parse_inline(&self.inline_source().replace("\\|", "|")));
+                    self.events.next();
+                }
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+        cells
+    }
+
+    /// Consume a leaf block's inline events up to and including its end tag
+    fn leaf(&mut self) -> Vec<Inline> {
+        let content = self.inline_run();
+        self.events.next();
+        content
+    }
+
+    /// Consume consecutive inline events and parse their source text
+    fn inline_run(&mut self) -> Vec<Inline> {
+        parse_inline(&self.inline_source())
+    }
+
+    /// Consume consecutive inline events and return their source text.
+    /// Pieces are re-joined per source line so escapes and wikilinks
+    /// split across events are preserved, while container prefixes
+    /// such as `> ` between lines are dropped.
+    fn inline_source(&mut self) -> String {
+        let mut lines: Vec<Range<usize>> = Vec::new();
+        let mut depth = 0usize;
+        let mut line_break = true;
+
+        while let Some((event, range)) = self.events.peek().cloned() {
+            match event {
+                Event::Start(ref tag) if is_inline_tag(tag) => depth += 1,
+                Event::End(_) if depth > 0 => depth -= 1,
+                Event::SoftBreak | Event::HardBreak if depth == 0 => {
+                    line_break = true;
+                    self.events.next();
+                    continue;
+                }
+                Event::TaskListMarker(_) => {
+                    self.events.next();
+                    continue;
+                }
+                Event::Text(_) | Event::Code(_) | Event::InlineHtml(_) | Event::FootnoteReference(_)
+                | Event::InlineMath(_) | Event::DisplayMath(_) | Event::SoftBreak | Event::HardBreak => {}
+                _ => break,
+            }
+            self.events.next();
+
+            if line_break {
+                lines.push(range);
+                line_break = false;
+            } else if let Some(last) = lines.last_mut() {
+                last.end = last.end.max(range.end);
+            }
+        }
+
+        let text: Vec<&str> = lines.into_iter().map(|r| self.source[r].trim_end()).collect();
+        text.join("\n")
+    }
+
+    /// Collect the literal text of a code or HTML block
+    fn raw_text(&mut self) -> String {
+        let mut text = String::new();
+        while let Some((event, _)) = self.events.next() {
+            match event {
+                Event::Text(t) | Event::Html(t) => text.push_str(&t),
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+        text
+    }
+
+    fn skip_container(&mut self) {
+        let mut depth = 1usize;
+        while let Some((event, _)) = self.events.next() {
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_inline_tag(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link { .. } | Tag::Image { .. }
+    )
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Render block-level elements to an HTML string (used for export)
+pub fn to_html(nodes: &[BlockNode], blocks: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            BlockNode::Paragraph(content) => {
+                out.push_str(&format!("<p>{}</p>", inline_to_html(content, blocks)));
+            }
+            BlockNode::Heading { level, content } => {
+                out.push_str(&format!("<h{0}>{1}</h{0}>", level, inline_to_html(content, blocks)));
+            }
+            BlockNode::CodeBlock { lang, code } => {
+                match lang {
+                    Some(lang) => out.push_str(&format!("<pre><code class=\"language-{}\">", escape_html(lang))),
+                    None => out.push_str("<pre><code>"),
+                }
+                out.push_str(&escape_html(code));
+                out.push_str("</code></pre>");
+            }
+            BlockNode::Math(tex) => {
+                out.push_str(&format!("<div class=\"math-display\">{}</div>", inline_to_html(&[Inline::DisplayMath(tex.clone())], blocks)));
+            }
+            BlockNode::Quote(children) => {
+                out.push_str(&format!("<blockquote>{}</blockquote>", to_html(children, blocks)));
+            }
+            BlockNode::List { start, tight, items } => {
+                let tag = if start.is_some() { "ol" } else { "ul" };
+                match start {
+                    Some(n) if *n != 1 => out.push_str(&format!("<ol start=\"{}\">", n)),
+                    _ => out.push_str(&format!("<{}>", tag)),
+                }
+                for item in items {
+                    out.push_str("<li>");
+                    if let Some(checked) = item.checked {
+                        out.push_str(if checked {
+                            "<input checked=\"\" disabled=\"\" type=\"checkbox\"> "
+                        } else {
+                            "<input disabled=\"\" type=\"checkbox\"> "
+                        });
+                    }
+                    for child in &item.content {
+                        match child {
+                            BlockNode::Paragraph(content) if *tight => out.push_str(&inline_to_html(content, blocks)),
+                            child => out.push_str(&to_html(std::slice::from_ref(child), blocks)),
+                        }
+                    }
+                    out.push_str("</li>");
+                }
+                out.push_str(&format!("</{}>", tag));
+            }
+            BlockNode::Table { alignments, head, rows } => {
+                let cell = |tag: &str, i: usize, content: &[Inline]| {
+                    let align = match alignments.get(i) {
+                        Some(ColumnAlign::Left) => " align=\"left\"",
+                        Some(ColumnAlign::Center) => " align=\"center\"",
+                        Some(ColumnAlign::Right) => " align=\"right\"",
+                        _ => "",
+                    };
+                    format!("<{0}{1}>{2}</{0}>", tag, align, inline_to_html(content, blocks))
+                };
+                out.push_str("<table><thead><tr>");
+                for (i, content) in head.iter().enumerate() {
+                    out.push_str(&cell("th", i, content));
+                }
+                out.push_str("</tr></thead>");
+                if !rows.is_empty() {
+                    out.push_str("<tbody>");
+                    for row in rows {
+                        out.push_str("<tr>");
+                        for (i, content) in row.iter().enumerate() {
+                            out.push_str(&cell("td", i, content));
+                        }
+                        out.push_str("</tr>");
+                    }
+                    out.push_str("</tbody>");
+                }
+                out.push_str("</table>");
+            }
+            BlockNode::FootnoteDefinition { label, content } => {
+                out.push_str(&format!(
+                    "<div class=\"footnote\" id=\"fn-{0}\"><sup>{0}</sup>{1}</div>",
+                    escape_html(label),
+                    to_html(content, blocks)
+                ));
+            }
+            BlockNode::Rule => out.push_str("<hr>"),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(src: &str) -> String {
+        to_html(&parse_blocks(src), &HashMap::new())
+    }
+
+    /// Block tags the spec runner's normalizer lets newlines surround
+    const BLOCK_TAGS: &[&str] = &[
+        "<p>", "</p>", "<ul>", "</ul>", "<ol>", "<ol ", "</ol>", "<li>", "</li>",
+        "<blockquote>", "</blockquote>", "<pre>", "</pre>", "<hr>",
+        "<h1>", "</h1>", "<h2>", "</h2>", "<h3>", "</h3>",
+        "<h4>", "</h4>", "<h5>", "</h5>", "<h6>", "</h6>",
+        "<table>", "</table>", "<thead>", "</thead>", "<tbody>", "</tbody>",
+        "<tr>", "</tr>", "<th", "</th>", "<td", "</td>",
+    ];
+
+    /// Normalize spec HTML the way the CommonMark test runner does:
+    /// newlines around block tags are insignificant and void tags
+    /// drop their trailing slash
+    fn normalize(html: &str) -> String {
+        let mut out = html.replace("<hr />", "<hr>").replace("<br />", "<br>");
+        for tag in BLOCK_TAGS {
+            out = out.replace(&format!("\n{}", tag), tag);
+            if tag.ends_with('>') {
+                out = out.replace(&format!("{}\n", tag), tag);
+            }
+        }
+        out
+    }
+
+    /// Examples from the CommonMark 0.31.2 and GFM 0.29 specs, with the
+    /// markdown and HTML exactly as the spec gives them. Not covered on
+    /// purpose: raw HTML, which is shown escaped rather than passed
+    /// through, and `#word`, `[[...]]` and `((...))`, which are outliner
+    /// syntax here rather than text.
+    fn check(section: &str, cases: &[(&str, &str)]) {
+        for (src, expected) in cases {
+            assert_eq!(html(src), normalize(expected), "{}: {:?}", section, src);
+        }
+    }
+
+    #[test]
+    fn thematic_breaks() {
+        check("4.1 Thematic breaks", &[
+            ("***\n---\n___\n", "<hr />\n<hr />\n<hr />\n"),
+            ("+++\n", "<p>+++</p>\n"),
+            ("--\n**\n__\n", "<p>--\n**\n__</p>\n"),
+            (" ***\n  ***\n   ***\n", "<hr />\n<hr />\n<hr />\n"),
+            ("- - -\n", "<hr />\n"),
+            ("Foo\n***\nbar\n", "<p>Foo</p>\n<hr />\n<p>bar</p>\n"),
+            ("Foo\n---\nbar\n", "<h2>Foo</h2>\n<p>bar</p>\n"),
+        ]);
+    }
+
+    #[test]
+    fn atx_headings() {
+        check("4.2 ATX headings", &[
+            ("# foo\n## foo\n### foo\n#### foo\n##### foo\n###### foo\n",
+             "<h1>foo</h1>\n<h2>foo</h2>\n<h3>foo</h3>\n<h4>foo</h4>\n<h5>foo</h5>\n<h6>foo</h6>\n"),
+            ("####### foo\n", "<p>####### foo</p>\n"),
+            ("# foo *bar* \\*baz\\*\n", "<h1>foo <em>bar</em> *baz*</h1>\n"),
+            ("## foo ##\n  ###   bar    ###\n", "<h2>foo</h2>\n<h3>bar</h3>\n"),
+            ("## \n#\n### ###\n", "<h2></h2>\n<h1></h1>\n<h3></h3>\n"),
+        ]);
+    }
+
+    #[test]
+    fn setext_headings() {
+        check("4.3 Setext headings", &[
+            ("Foo *bar*\n=========\n\nFoo *bar*\n---------\n", "<h1>Foo <em>bar</em></h1>\n<h2>Foo <em>bar</em></h2>\n"),
+            ("Foo *bar\nbaz*\n====\n", "<h1>Foo <em>bar\nbaz</em></h1>\n"),
+        ]);
+    }
+
+    #[test]
+    fn indented_code_blocks() {
+        check("4.4 Indented code blocks", &[
+            ("    a simple\n      indented code block\n", "<pre><code>a simple\n  indented code block\n</code></pre>\n"),
+            ("    chunk1\n\n    chunk2\n  \n \n \n    chunk3\n", "<pre><code>chunk1\n\nchunk2\n\n\n\nchunk3\n</code></pre>\n"),
+        ]);
+    }
+
+    #[test]
+    fn fenced_code_blocks() {
+        check("4.5 Fenced code blocks", &[
+            ("```\n<\n >\n```\n", "<pre><code>&lt;\n &gt;\n</code></pre>\n"),
+            ("~~~\n<\n >\n~~~\n", "<pre><code>&lt;\n &gt;\n</code></pre>\n"),
+            ("````\naaa\n```\n``````\n", "<pre><code>aaa\n```\n</code></pre>\n"),
+            ("```\n", "<pre><code></code></pre>\n"),
+            ("`````\n\n```\naaa\n", "<pre><code>\n```\naaa\n</code></pre>\n"),
+            ("> ```\n> aaa\n\nbbb\n", "<blockquote>\n<pre><code>aaa\n</code></pre>\n</blockquote>\n<p>bbb</p>\n"),
+            ("```\n\n  \n```\n", "<pre><code>\n  \n</code></pre>\n"),
+            ("```ruby\ndef foo(x)\n  return 3\nend\n```\n",
+             "<pre><code class=\"language-ruby\">def foo(x)\n  return 3\nend\n</code></pre>\n"),
+            ("~~~~    ruby startline=3 $%@#$\ndef foo(x)\n  return 3\nend\n~~~~~~~\n",
+             "<pre><code class=\"language-ruby\">def foo(x)\n  return 3\nend\n</code></pre>\n"),
+        ]);
+    }
+
+    #[test]
+    fn block_quotes() {
+        check("5.1 Block quotes", &[
+            ("> # Foo\n> bar\n> baz\n", "<blockquote>\n<h1>Foo</h1>\n<p>bar\nbaz</p>\n</blockquote>\n"),
+            ("># Foo\n>bar\n> baz\n", "<blockquote>\n<h1>Foo</h1>\n<p>bar\nbaz</p>\n</blockquote>\n"),
+            ("> # Foo\n> bar\nbaz\n", "<blockquote>\n<h1>Foo</h1>\n<p>bar\nbaz</p>\n</blockquote>\n"),
+            ("> foo\n---\n", "<blockquote>\n<p>foo</p>\n</blockquote>\n<hr />\n"),
+            ("> - foo\n- bar\n", "<blockquote>\n<ul>\n<li>foo</li>\n</ul>\n</blockquote>\n<ul>\n<li>bar</li>\n</ul>\n"),
+            (">\n", "<blockquote>\n</blockquote>\n"),
+            ("> foo\n\n> bar\n", "<blockquote>\n<p>foo</p>\n</blockquote>\n<blockquote>\n<p>bar</p>\n</blockquote>\n"),
+            ("> > > foo\nbar\n", "<blockquote>\n<blockquote>\n<blockquote>\n<p>foo\nbar</p>\n</blockquote>\n</blockquote>\n</blockquote>\n"),
+        ]);
+    }
+
+    #[test]
+    fn list_items() {
+        check("5.2 List items", &[
+            ("1.  A paragraph\n    with two lines.\n\n        indented code\n\n    > A block quote.\n",
+             "<ol>\n<li>\n<p>A paragraph\nwith two lines.</p>\n<pre><code>indented code\n</code></pre>\n<blockquote>\n<p>A block quote.</p>\n</blockquote>\n</li>\n</ol>\n"),
+            ("- one\n\n two\n", "<ul>\n<li>one</li>\n</ul>\n<p>two</p>\n"),
+            ("- one\n\n  two\n", "<ul>\n<li>\n<p>one</p>\n<p>two</p>\n</li>\n</ul>\n"),
+            ("-one\n\n2.two\n", "<p>-one</p>\n<p>2.two</p>\n"),
+            ("123456789. ok\n", "<ol start=\"123456789\">\n<li>ok</li>\n</ol>\n"),
+            ("0. ok\n", "<ol start=\"0\">\n<li>ok</li>\n</ol>\n"),
+            ("- foo\n  - bar\n    - baz\n      - boo\n",
+             "<ul>\n<li>foo\n<ul>\n<li>bar\n<ul>\n<li>baz\n<ul>\n<li>boo</li>\n</ul>\n</li>\n</ul>\n</li>\n</ul>\n</li>\n</ul>\n"),
+        ]);
+    }
+
+    #[test]
+    fn lists() {
+        check("5.3 Lists", &[
+            ("- foo\n- bar\n+ baz\n", "<ul>\n<li>foo</li>\n<li>bar</li>\n</ul>\n<ul>\n<li>baz</li>\n</ul>\n"),
+            ("1. foo\n2. bar\n3) baz\n", "<ol>\n<li>foo</li>\n<li>bar</li>\n</ol>\n<ol start=\"3\">\n<li>baz</li>\n</ol>\n"),
+            ("Foo\n- bar\n- baz\n", "<p>Foo</p>\n<ul>\n<li>bar</li>\n<li>baz</li>\n</ul>\n"),
+            ("The number of windows in my house is\n14.  The number of doors is 6.\n",
+             "<p>The number of windows in my house is\n14.  The number of doors is 6.</p>\n"),
+            ("- a\n- b\n\n- c\n", "<ul>\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n<li>\n<p>c</p>\n</li>\n</ul>\n"),
+            ("- a\n  - b\n\n    c\n- d\n", "<ul>\n<li>a\n<ul>\n<li>\n<p>b</p>\n<p>c</p>\n</li>\n</ul>\n</li>\n<li>d</li>\n</ul>\n"),
+            ("- a\n  > b\n  ```\n  c\n  ```\n- d\n",
+             "<ul>\n<li>a\n<blockquote>\n<p>b</p>\n</blockquote>\n<pre><code>c\n</code></pre>\n</li>\n<li>d</li>\n</ul>\n"),
+            ("* foo\n  * bar\n\n  baz\n", "<ul>\n<li>\n<p>foo</p>\n<ul>\n<li>bar</li>\n</ul>\n<p>baz</p>\n</li>\n</ul>\n"),
+        ]);
+    }
+
+    #[test]
+    fn task_lists() {
+        check("GFM 5.3 Task list items", &[
+            ("- [ ] foo\n- [x] bar\n",
+             "<ul>\n<li><input disabled=\"\" type=\"checkbox\"> foo</li>\n<li><input checked=\"\" disabled=\"\" type=\"checkbox\"> bar</li>\n</ul>\n"),
+            ("- [x] foo\n  - [ ] bar\n  - [x] baz\n- [ ] bim\n",
+             "<ul>\n<li><input checked=\"\" disabled=\"\" type=\"checkbox\"> foo\n<ul>\n<li><input disabled=\"\" type=\"checkbox\"> bar</li>\n<li><input checked=\"\" disabled=\"\" type=\"checkbox\"> baz</li>\n</ul>\n</li>\n<li><input disabled=\"\" type=\"checkbox\"> bim</li>\n</ul>\n"),
+        ]);
+    }
+
+    #[test]
+    fn tables() {
+        check("GFM 4.10 Tables", &[
+            ("| foo | bar |\n| --- | --- |\n| baz | bim |\n",
+             "<table>\n<thead>\n<tr>\n<th>foo</th>\n<th>bar</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>baz</td>\n<td>bim</td>\n</tr>\n</tbody>\n</table>\n"),
+            ("| abc | defghi |\n:-: | -----------:\nbar | baz\n",
+             "<table>\n<thead>\n<tr>\n<th align=\"center\">abc</th>\n<th align=\"right\">defghi</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td align=\"center\">bar</td>\n<td align=\"right\">baz</td>\n</tr>\n</tbody>\n</table>\n"),
+            ("| f\\|oo  |\n| ------ |\n| b `\\|` az |\n| b **\\|** im |\n",
+             "<table>\n<thead>\n<tr>\n<th>f|oo</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>b <code>|</code> az</td>\n</tr>\n<tr>\n<td>b <strong>|</strong> im</td>\n</tr>\n</tbody>\n</table>\n"),
+            ("| abc | def |\n| --- |\n| bar |\n", "<p>| abc | def |\n| --- |\n| bar |</p>\n"),
+            ("| abc | def |\n| --- | --- |\n",
+             "<table>\n<thead>\n<tr>\n<th>abc</th>\n<th>def</th>\n</tr>\n</thead>\n</table>\n"),
+        ]);
+    }
+
+    #[test]
+    fn strikethrough() {
+        check("GFM 6.5 Strikethrough", &[
+            ("~~Hi~~ Hello, world!\n", "<p><del>Hi</del> Hello, world!</p>\n"),
+        ]);
+    }
+
+    #[test]
+    fn outliner_syntax_survives_block_parsing() {
+        assert_eq!(
+            parse_blocks("- see [[Rust Book]] #reading"),
+            vec![BlockNode::List {
+                start: None,
+                tight: true,
+                items: vec![ListItem {
+                    checked: None,
+                    content: vec![BlockNode::Paragraph(vec![
+                        Inline::Text("see ".into()),
+                        Inline::PageRef { page: "Rust Book".into(), label: None },
+                        Inline::Text(" ".into()),
+                        Inline::Tag("reading".into()),
+                    ])],
+                }],
+            }]
+        );
+        assert_eq!(parse_blocks("$$x^2$$"), vec![BlockNode::Math("x^2".into())]);
+    }
+}
@@ -0,0 +1,414 @@
+use crate::store::Theme;
+
+/// Kind of a highlighted token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    Literal,
+    String,
+    Number,
+    Comment,
+    Type,
+    Function,
+    Property,
+    Operator,
+}
+
+/// A run of source text with a single token kind
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// Lexical rules for a supported language
+struct Language {
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    /// Identifiers followed by `=` or `:` are keys (JSON, TOML)
+    keyed: bool,
+    /// Keywords are matched case-insensitively (SQL)
+    case_insensitive: bool,
+}
+
+const RUST: Language = Language {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+        "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+        "return", "self", "Self", "static", "struct", "super", "trait", "type", "unsafe", "use",
+        "where", "while",
+    ],
+    literals: &["true", "false", "None", "Some", "Ok", "Err"],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    keyed: false,
+    case_insensitive: false,
+};
+
+const PYTHON: Language = Language {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+        "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+        "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+    ],
+    literals: &["True", "False", "None", "self"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keyed: false,
+    case_insensitive: false,
+};
+
+const JAVASCRIPT: Language = Language {
+    keywords: &[
+        "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "delete",
+        "do", "else", "export", "extends", "finally", "for", "from", "function", "if", "import", "in",
+        "instanceof", "let", "new", "of", "return", "static", "switch", "throw", "try", "typeof",
+        "var", "void", "while", "yield", "interface", "type", "enum", "implements", "private",
+        "public", "protected", "readonly", "as", "keyof",
+    ],
+    literals: &["true", "false", "null", "undefined", "this", "NaN"],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    keyed: false,
+    case_insensitive: false,
+};
+
+const SQL: Language = Language {
+    keywords: &[
+        "select", "from", "where", "insert", "into", "values", "update", "set", "delete", "create",
+        "table", "drop", "alter", "index", "join", "left", "right", "inner", "outer", "on", "and",
+        "or", "not", "in", "is", "as", "group", "by", "order", "having", "limit", "offset",
+        "distinct", "union", "all", "primary", "key", "foreign", "references", "default", "with",
+        "case", "when", "then", "else", "end", "asc", "desc", "like", "between", "exists",
+    ],
+    literals: &["null", "true", "false"],
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['\'', '"'],
+    keyed: false,
+    case_insensitive: true,
+};
+
+const SHELL: Language = Language {
+    keywords: &[
+        "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+        "in", "function", "return", "export", "local", "readonly", "source", "echo", "cd", "exit",
+    ],
+    literals: &["true", "false"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keyed: false,
+    case_insensitive: false,
+};
+
+const JSON: Language = Language {
+    keywords: &[],
+    literals: &["true", "false", "null"],
+    line_comments: &[],
+    block_comment: None,
+    quotes: &['"'],
+    keyed: true,
+    case_insensitive: false,
+};
+
+const TOML: Language = Language {
+    keywords: &[],
+    literals: &["true", "false"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keyed: true,
+    case_insensitive: false,
+};
+
+fn language_for(lang: &str) -> Option<&'static Language> {
+    match lang.to_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "python" | "py" => Some(&PYTHON),
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => Some(&JAVASCRIPT),
+        "sql" => Some(&SQL),
+        "shell" | "sh" | "bash" | "zsh" | "console" => Some(&SHELL),
+        "json" | "jsonc" => Some(&JSON),
+        "toml" => Some(&TOML),
+        _ => None,
+    }
+}
+
+/// Whether a fence language has highlighting rules
+pub fn is_supported(lang: &str) -> bool {
+    language_for(lang).is_some()
+}
+
+/// Highlight source code, returning tokens grouped by line.
+/// Unknown languages produce a single plain token per line.
+pub fn highlight(code: &str, lang: Option<&str>) -> Vec<Vec<Token>> {
+    let language = match lang.and_then(language_for) {
+        Some(language) => language,
+        None => {
+            return code
+                .split('\n')
+                .map(|line| vec![Token { kind: TokenKind::Plain, text: line.to_string() }])
+                .collect();
+        }
+    };
+
+    let mut lines = vec![Vec::new()];
+    for token in tokenize(code, language) {
+        // Multi-line tokens (block comments, strings) are split at newlines
+        let mut parts = token.text.split('\n');
+        if let Some(first) = parts.next() {
+            push_token(lines.last_mut().unwrap(), token.kind, first);
+        }
+        for part in parts {
+            lines.push(Vec::new());
+            push_token(lines.last_mut().unwrap(), token.kind, part);
+        }
+    }
+    lines
+}
+
+fn push_token(line: &mut Vec<Token>, kind: TokenKind, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match line.last_mut() {
+        Some(last) if last.kind == kind => last.text.push_str(text),
+        _ => line.push(Token { kind, text: text.to_string() }),
+    }
+}
+
+fn tokenize(code: &str, language: &Language) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < code.len() {
+        let rest = &code[i..];
+        let ch = rest.chars().next().unwrap_or_default();
+
+        let (kind, len) = if language.line_comments.iter().any(|p| rest.starts_with(p)) {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some((open, close)) = language.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+            let end = rest[open.len()..].find(close).map(|e| e + open.len() + close.len()).unwrap_or(rest.len());
+            (TokenKind::Comment, end)
+        } else if language.quotes.contains(&ch) {
+            let len = string_len(rest, ch);
+            let is_key = language.keyed && rest[len..].trim_start().starts_with(|c| c == ':' || c == '=');
+            (if is_key { TokenKind::Property } else { TokenKind::String }, len)
+        } else if ch.is_ascii_digit() {
+            let len = rest
+                .char_indices()
+                .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '.' || *c == '_'))
+                .map(|(idx, _)| idx)
+                .unwrap_or(rest.len());
+            (TokenKind::Number, len)
+        } else if ch.is_alphabetic() || ch == '_' || (ch == '$' && language.quotes.contains(&'`')) {
+            let len = rest
+                .char_indices()
+                .find(|(idx, c)| *idx > 0 && !(c.is_alphanumeric() || *c == '_' || (*c == '-' && language.keyed)))
+                .map(|(idx, _)| idx)
+                .unwrap_or(rest.len());
+            (word_kind(&rest[..len], &rest[len..], language), len)
+        } else if "+-*/%=<>!&|^~?:".contains(ch) {
+            (TokenKind::Operator, ch.len_utf8())
+        } else {
+            (TokenKind::Plain, ch.len_utf8())
+        };
+
+        let len = len.max(ch.len_utf8());
+        match tokens.last_mut() {
+            Some(Token { kind: last_kind, text }) if *last_kind == kind => text.push_str(&rest[..len]),
+            _ => tokens.push(Token { kind, text: rest[..len].to_string() }),
+        }
+        i += len;
+    }
+
+    tokens
+}
+
+/// Length of a quoted string starting at `rest`, honoring backslash escapes
+fn string_len(rest: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (idx, c) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return idx + c.len_utf8();
+        } else if c == '\n' && quote != '`' {
+            return idx;
+        }
+    }
+    rest.len()
+}
+
+fn word_kind(word: &str, after: &str, language: &Language) -> TokenKind {
+    let matches = |list: &[&str]| {
+        if language.case_insensitive {
+            list.iter().any(|k| k.eq_ignore_ascii_case(word))
+        } else {
+            list.contains(&word)
+        }
+    };
+
+    if matches(language.keywords) {
+        TokenKind::Keyword
+    } else if matches(language.literals) {
+        TokenKind::Literal
+    } else if language.keyed && after.trim_start().starts_with('=') {
+        TokenKind::Property
+    } else if after.starts_with('(') || after.starts_with("!(") {
+        TokenKind::Function
+    } else if word.starts_with(|c: char| c.is_uppercase()) {
+        TokenKind::Type
+    } else {
+        TokenKind::Plain
+    }
+}
+
+/// Whether code is drawn with the dark palette.
+/// `Theme::System` follows the `prefers-color-scheme` media query, so
+/// resolve this once per code block rather than once per token.
+pub fn is_dark(theme: &Theme) -> bool {
+    match theme {
+        Theme::Dark => true,
+        Theme::Light => false,
+        Theme::System => prefers_dark(),
+    }
+}
+
+/// Token color for the light or dark palette
+pub fn token_color(kind: TokenKind, dark: bool) -> &'static str {
+    match (kind, dark) {
+        (TokenKind::Plain, false) => "#3d3f49",
+        (TokenKind::Plain, true) => "#e3e4e8",
+        (TokenKind::Keyword, false) => "#a626a4",
+        (TokenKind::Keyword, true) => "#c678dd",
+        (TokenKind::Literal, false) => "#986801",
+        (TokenKind::Literal, true) => "#d19a66",
+        (TokenKind::String, false) => "#50a14f",
+        (TokenKind::String, true) => "#98c379",
+        (TokenKind::Number, false) => "#986801",
+        (TokenKind::Number, true) => "#d19a66",
+        (TokenKind::Comment, false) => "#a0a1a7",
+        (TokenKind::Comment, true) => "#767b8f",
+        (TokenKind::Type, false) => "#c18401",
+        (TokenKind::Type, true) => "#e5c07b",
+        (TokenKind::Function, false) => "#4078f2",
+        (TokenKind::Function, true) => "#61afef",
+        (TokenKind::Property, false) => "#e45649",
+        (TokenKind::Property, true) => "#e06c75",
+        (TokenKind::Operator, false) => "#0184bc",
+        (TokenKind::Operator, true) => "#56b6c2",
+    }
+}
+
+#[cfg(feature = "web")]
+fn prefers_dark() -> bool {
+    web_sys::window()
+        .and_then(|w| w.match_media("(prefers-color-scheme: dark)").ok().flatten())
+        .map(|m| m.matches())
+        .unwrap_or(false)
+}
+
+#[cfg(not(feature = "web"))]
+fn prefers_dark() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kind of the first token spelling `text`
+    fn kind(code: &str, lang: &str, text: &str) -> TokenKind {
+        highlight(code, Some(lang))
+            .into_iter()
+            .flatten()
+            .find(|token| token.text == text)
+            .unwrap_or_else(|| panic!("no token {:?} in {:?}", text, code))
+            .kind
+    }
+
+    #[test]
+    fn rust_tokens() {
+        let code = "fn main() { let s = \"hi\"; } // done";
+        assert_eq!(kind(code, "rust", "fn"), TokenKind::Keyword);
+        assert_eq!(kind(code, "rust", "main"), TokenKind::Function);
+        assert_eq!(kind(code, "rust", "\"hi\""), TokenKind::String);
+        assert_eq!(kind(code, "rust", "// done"), TokenKind::Comment);
+        assert_eq!(kind("/* a\nb */ x", "rs", "/* a"), TokenKind::Comment);
+        assert_eq!(kind("/* a\nb */ x", "rs", "b */"), TokenKind::Comment);
+    }
+
+    #[test]
+    fn python_tokens() {
+        let code = "def f(): return 'it\\'s' # note";
+        assert_eq!(kind(code, "python", "def"), TokenKind::Keyword);
+        assert_eq!(kind(code, "python", "return"), TokenKind::Keyword);
+        assert_eq!(kind(code, "python", "'it\\'s'"), TokenKind::String);
+        assert_eq!(kind(code, "py", "# note"), TokenKind::Comment);
+    }
+
+    #[test]
+    fn javascript_tokens() {
+        let code = "const s = `a ${b}`; /* c */ // d";
+        assert_eq!(kind(code, "javascript", "const"), TokenKind::Keyword);
+        assert_eq!(kind(code, "js", "`a ${b}`"), TokenKind::String);
+        assert_eq!(kind(code, "ts", "/* c */"), TokenKind::Comment);
+        assert_eq!(kind(code, "ts", "// d"), TokenKind::Comment);
+    }
+
+    #[test]
+    fn sql_tokens_ignore_case() {
+        let code = "SELECT name FROM users WHERE id = 'x' -- one";
+        assert_eq!(kind(code, "sql", "SELECT"), TokenKind::Keyword);
+        assert_eq!(kind(code, "sql", "FROM"), TokenKind::Keyword);
+        assert_eq!(kind(code, "sql", "'x'"), TokenKind::String);
+        assert_eq!(kind(code, "sql", "-- one"), TokenKind::Comment);
+    }
+
+    #[test]
+    fn shell_tokens() {
+        let code = "if true; then echo \"$HOME\"; fi # end";
+        assert_eq!(kind(code, "bash", "if"), TokenKind::Keyword);
+        assert_eq!(kind(code, "sh", "echo"), TokenKind::Keyword);
+        assert_eq!(kind(code, "sh", "\"$HOME\""), TokenKind::String);
+        assert_eq!(kind(code, "sh", "# end"), TokenKind::Comment);
+    }
+
+    #[test]
+    fn json_keys_and_values() {
+        let code = "{\"name\": \"brain\", \"ok\": true}";
+        assert_eq!(kind(code, "json", "\"name\""), TokenKind::Property);
+        assert_eq!(kind(code, "json", "\"brain\""), TokenKind::String);
+        assert_eq!(kind(code, "json", "true"), TokenKind::Literal);
+    }
+
+    #[test]
+    fn toml_keys_strings_and_comments() {
+        let code = "name = \"brain\" # package\nedition-year = 2021";
+        assert_eq!(kind(code, "toml", "name"), TokenKind::Property);
+        assert_eq!(kind(code, "toml", "edition-year"), TokenKind::Property);
+        assert_eq!(kind(code, "toml", "\"brain\""), TokenKind::String);
+        assert_eq!(kind(code, "toml", "# package"), TokenKind::Comment);
+        assert_eq!(kind(code, "toml", "2021"), TokenKind::Number);
+    }
+
+    #[test]
+    fn unknown_languages_stay_plain() {
+        let plain = |text: &str| vec![Token { kind: TokenKind::Plain, text: text.to_string() }];
+        assert_eq!(highlight("fn x() // y\nz", Some("cobol")), vec![plain("fn x() // y"), plain("z")]);
+        assert_eq!(highlight("let", None), vec![plain("let")]);
+        assert!(!is_supported("cobol"));
+        assert!(is_supported("TOML"));
+    }
+}
@@ -0,0 +1,654 @@
+use std::collections::HashMap;
+
+/// A single inline element of block content
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Strong(Vec<Inline>),
+    Emphasis(Vec<Inline>),
+    Strikethrough(Vec<Inline>),
+    Highlight(Vec<Inline>),
+    /// Code span, kept verbatim and never formatted
+    Code(String),
+    /// `[[Page]]` or `[[Page|alias]]`
+    PageRef { page: String, label: Option<String> },
+    /// `#tag` or `#[[multi word tag]]`, stored without the leading `#`
+    Tag(String),
+    /// `((block-id))`
+    BlockRef(String),
+    /// `$...$` inline math (raw LaTeX)
+    Math(String),
+    /// `$$...$$` display math (raw LaTeX)
+    DisplayMath(String),
+    /// `[^label]` footnote reference
+    FootnoteRef(String),
+    /// Bare `https://...` URL or `[label](url)` link
+    Link { href: String, label: Vec<Inline> },
+    LineBreak,
+}
+
+/// Delimited formatting spans, longest delimiters first
+const SPANS: &[(&str, fn(Vec<Inline>) -> Inline)] = &[
+    ("**", Inline::Strong),
+    ("~~", Inline::Strikethrough),
+    ("==", Inline::Highlight),
+    ("*", Inline::Emphasis),
+    ("_", Inline::Emphasis),
+];
+
+/// Parse block content into inline elements
+pub fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut nodes = Vec::new();
+    let mut buf = String::new();
+    let mut prev: Option<char> = None;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+
+        if let Some((node, consumed)) = parse_special(rest, prev) {
+            flush_text(&mut buf, &mut nodes);
+            nodes.push(node);
+            prev = text[..i + consumed].chars().last();
+            i += consumed;
+            continue;
+        }
+
+        let ch = rest.chars().next().unwrap_or_default();
+
+        // Backslash escapes any ASCII punctuation
+        if ch == '\\' {
+            if let Some(next) = rest[1..].chars().next().filter(|c| c.is_ascii_punctuation()) {
+                buf.push(next);
+                prev = Some(next);
+                i += 1 + next.len_utf8();
+                continue;
+            }
+        }
+
+        if ch == '\n' {
+            flush_text(&mut buf, &mut nodes);
+            nodes.push(Inline::LineBreak);
+        } else {
+            buf.push(ch);
+        }
+        prev = Some(ch);
+        i += ch.len_utf8();
+    }
+
+    flush_text(&mut buf, &mut nodes);
+    nodes
+}
+
+/// Try to parse a non-text element at the start of `rest`.
+/// Returns the element and the number of bytes consumed.
+fn parse_special(rest: &str, prev: Option<char>) -> Option<(Inline, usize)> {
+    let first = rest.chars().next()?;
+
+    match first {
+        '`' => parse_code_span(rest),
+        '$' => parse_math(rest, prev),
+        '[' if rest.starts_with("[[") => parse_page_ref(rest),
+        '[' if rest.starts_with("[^") => {
+            let end = rest.find(']')?;
+            let label = &rest[2..end];
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                return None;
+            }
+            Some((Inline::FootnoteRef(label.to_string()), end + 1))
+        }
+        '[' => parse_markdown_link(rest),
+        '(' if rest.starts_with("((") => {
+            let end = rest[2..].find("))")?;
+            let id = rest[2..2 + end].trim();
+            if id.is_empty() || id.contains(char::is_whitespace) {
+                return None;
+            }
+            Some((Inline::BlockRef(id.to_string()), end + 4))
+        }
+        '#' if prev.map_or(true, |c| c.is_whitespace() || c == '(') => parse_tag(rest),
+        'h' if prev.map_or(true, |c| !c.is_alphanumeric()) => parse_url(rest),
+        _ => parse_span(rest, prev),
+    }
+}
+
+fn parse_code_span(rest: &str) -> Option<(Inline, usize)> {
+    let ticks = rest.chars().take_while(|&c| c == '`').count();
+    let fence = &rest[..ticks];
+    let body = &rest[ticks..];
+
+    // The closing run must be exactly as long as the opening run
+    let mut search = 0;
+    while let Some(pos) = body[search..].find(fence) {
+        let start = search + pos;
+        let run = body[start..].chars().take_while(|&c| c == '`').count();
+        if run == ticks {
+            let code = body[..start].replace('\n', " ");
+            let code = if code.len() > 2 && code.starts_with(' ') && code.ends_with(' ') {
+                code[1..code.len() - 1].to_string()
+            } else {
+                code
+            };
+            return Some((Inline::Code(code), ticks * 2 + start));
+        }
+        search = start + run;
+    }
+
+    None
+}
+
+/// Parse `$$display$$` or `$inline$` math.
+/// Inline math follows the pandoc rule: the opening `$` must be followed by
+/// a non-space and the closing `$` must not be preceded by a space or followed
+/// by a digit, so prices like "$5 and $10" stay plain text.
+fn parse_math(rest: &str, prev: Option<char>) -> Option<(Inline, usize)> {
+    if prev == Some('$') {
+        return None;
+    }
+
+    if let Some(body) = rest.strip_prefix("$$") {
+        let end = find_unescaped(body, "$$")?;
+        let tex = body[..end].trim();
+        if tex.is_empty() {
+            return None;
+        }
+        return Some((Inline::DisplayMath(tex.to_string()), end + 4));
+    }
+
+    let body = &rest[1..];
+    if body.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let end = find_unescaped(body, "$")?;
+    let tex = &body[..end];
+    if tex.is_empty() || tex.ends_with(char::is_whitespace) || tex.contains('\n') {
+        return None;
+    }
+    if body[end + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((Inline::Math(tex.to_string()), end + 2))
+}
+
+/// Find `delim` in `body`, skipping backslash-escaped characters
+fn find_unescaped(body: &str, delim: &str) -> Option<usize> {
+    let mut i = 0;
+    while i < body.len() {
+        let rest = &body[i..];
+        if rest.starts_with('\\') {
+            i += 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if rest.starts_with(delim) {
+            return Some(i);
+        }
+        i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+fn parse_page_ref(rest: &str) -> Option<(Inline, usize)> {
+    let end = rest[2..].find("]]")?;
+    let content = &rest[2..2 + end];
+    if content.trim().is_empty() || content.contains('\n') {
+        return None;
+    }
+
+    let node = match content.split_once('|') {
+        Some((page, label)) => Inline::PageRef {
+            page: page.trim().to_string(),
+            label: Some(label.trim().to_string()),
+        },
+        None => Inline::PageRef {
+            page: content.trim().to_string(),
+            label: None,
+        },
+    };
+
+    Some((node, end + 4))
+}
+
+fn parse_markdown_link(rest: &str) -> Option<(Inline, usize)> {
+    let label_end = rest.find("](")?;
+    let label = &rest[1..label_end];
+    if label.contains('\n') {
+        return None;
+    }
+    let href_start = label_end + 2;
+    let href_end = rest[href_start..].find(')')?;
+    let href = rest[href_start..href_start + href_end].trim();
+    if href.is_empty() || href.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some((
+        Inline::Link {
+            href: href.to_string(),
+            label: parse_inline(label),
+        },
+        href_start + href_end + 1,
+    ))
+}
+
+fn parse_tag(rest: &str) -> Option<(Inline, usize)> {
+    if rest.starts_with("#[[") {
+        let end = rest[3..].find("]]")?;
+        let tag = rest[3..3 + end].trim();
+        if tag.is_empty() {
+            return None;
+        }
+        return Some((Inline::Tag(tag.to_string()), end + 5));
+    }
+
+    let len: usize = rest[1..]
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-' || *c == '/')
+        .map(char::len_utf8)
+        .sum();
+    if len == 0 {
+        return None;
+    }
+
+    Some((Inline::Tag(rest[1..1 + len].to_string()), len + 1))
+}
+
+fn parse_url(rest: &str) -> Option<(Inline, usize)> {
+    if !rest.starts_with("https://") && !rest.starts_with("http://") {
+        return None;
+    }
+
+    let mut len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    // Trailing punctuation belongs to the sentence, not the URL
+    while len > 0 && rest[..len].ends_with(|c: char| matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | ')' | '"' | '\'')) {
+        len -= 1;
+    }
+
+    let href = &rest[..len];
+    if href.ends_with("://") {
+        return None;
+    }
+
+    Some((
+        Inline::Link {
+            href: href.to_string(),
+            label: vec![Inline::Text(href.to_string())],
+        },
+        len,
+    ))
+}
+
+fn parse_span(rest: &str, prev: Option<char>) -> Option<(Inline, usize)> {
+    for (delim, make) in SPANS {
+        if !rest.starts_with(delim) {
+            continue;
+        }
+        // `_` only opens emphasis at a word boundary (snake_case stays as text)
+        if *delim == "_" && prev.map_or(false, char::is_alphanumeric) {
+            return None;
+        }
+
+        let body = &rest[delim.len()..];
+        if body.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let end = find_closing(body, delim)?;
+        let inner = &body[..end];
+        if inner.is_empty() || inner.ends_with(char::is_whitespace) {
+            return None;
+        }
+
+        return Some((make(parse_inline(inner)), delim.len() * 2 + end));
+    }
+
+    None
+}
+
+/// Find the closing delimiter, skipping over code spans and links
+fn find_closing(body: &str, delim: &str) -> Option<usize> {
+    let mut i = 0;
+    while i < body.len() {
+        let rest = &body[i..];
+        if rest.starts_with('\\') {
+            i += 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if rest.starts_with('`') || rest.starts_with('$') || rest.starts_with("[[") || rest.starts_with("((") {
+            if let Some((_, consumed)) = parse_special(rest, None) {
+                i += consumed;
+                continue;
+            }
+        }
+        if rest.starts_with(delim) {
+            // A single `*` must not close on half of a `**`
+            let doubled = delim.len() == 1 && rest[1..].starts_with(delim);
+            if !doubled {
+                return Some(i);
+            }
+            i += 2;
+            continue;
+        }
+        i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+fn flush_text(buf: &mut String, nodes: &mut Vec<Inline>) {
+    if buf.is_empty() {
+        return;
+    }
+    match nodes.last_mut() {
+        Some(Inline::Text(text)) => text.push_str(buf),
+        _ => nodes.push(Inline::Text(buf.clone())),
+    }
+    buf.clear();
+}
+
+/// Split `key:: value` property lines from the rest of the block content
+pub fn split_properties(text: &str) -> (Vec<(String, String)>, String) {
+    let mut properties = Vec::new();
+    let mut body = Vec::new();
+
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        // Lines inside fenced code blocks are never properties
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        match parse_property_line(line).filter(|_| !in_fence) {
+            Some(prop) => properties.push(prop),
+            None => body.push(line),
+        }
+    }
+
+    (properties, body.join("\n"))
+}
+
+/// Set a `key:: value` property line, replacing an existing one for `key`
+pub fn set_property(text: &str, key: &str, value: &str) -> String {
+    let line = format!("{}:: {}", key, value);
+    let mut replaced = false;
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|l| match parse_property_line(l) {
+            Some((k, _)) if k == key && !replaced => {
+                replaced = true;
+                line.clone()
+            }
+            _ => l.to_string(),
+        })
+        .collect();
+    if !replaced {
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Parse a single `key:: value` line
+pub fn parse_property_line(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.split_once("::")?;
+    let key = key.trim();
+    if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        return None;
+    }
+    Some((key.to_string(), value.trim().to_string()))
+}
+
+/// Every `#tag` in inline elements, nested formatting included, in order
+pub fn collect_tags(nodes: &[Inline]) -> Vec<String> {
+    let mut tags = Vec::new();
+    for node in nodes {
+        match node {
+            Inline::Tag(tag) => tags.push(tag.clone()),
+            Inline::Strong(children)
+            | Inline::Emphasis(children)
+            | Inline::Strikethrough(children)
+            | Inline::Highlight(children)
+            | Inline::Link { label: children, .. } => tags.extend(collect_tags(children)),
+            _ => {}
+        }
+    }
+    tags
+}
+
+/// Collect the plain text of inline elements (used for titles and previews)
+pub fn plain_text(nodes: &[Inline]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            Inline::Text(text) | Inline::Code(text) | Inline::Math(text) | Inline::DisplayMath(text) => out.push_str(text),
+            Inline::Strong(children)
+            | Inline::Emphasis(children)
+            | Inline::Strikethrough(children)
+            | Inline::Highlight(children) => out.push_str(&plain_text(children)),
+            Inline::PageRef { page, label } => out.push_str(label.as_deref().unwrap_or(page)),
+            Inline::Tag(tag) => {
+                out.push('#');
+                out.push_str(tag);
+            }
+            Inline::BlockRef(id) => out.push_str(id),
+            Inline::FootnoteRef(label) => {
+                out.push('[');
+                out.push_str(label);
+                out.push(']');
+            }
+            Inline::Link { label, .. } => out.push_str(&plain_text(label)),
+            Inline::LineBreak => out.push(' '),
+        }
+    }
+    out
+}
+
+/// Render inline elements to an HTML string (used for export)
+pub fn to_html(nodes: &[Inline], blocks: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            Inline::Text(text) => out.push_str(&escape_html(text)),
+            Inline::Strong(children) => wrap(&mut out, "strong", children, blocks),
+            Inline::Emphasis(children) => wrap(&mut out, "em", children, blocks),
+            Inline::Strikethrough(children) => wrap(&mut out, "del", children, blocks),
+            Inline::Highlight(children) => wrap(&mut out, "mark", children, blocks),
+            Inline::Code(code) => {
+                out.push_str("<code>");
+                out.push_str(&escape_html(code));
+                out.push_str("</code>");
+            }
+            Inline::PageRef { page, label } => {
+                out.push_str(&format!(
+                    "<a class=\"page-ref\" data-page=\"{}\">{}</a>",
+                    escape_html(page),
+                    escape_html(label.as_deref().unwrap_or(page))
+                ));
+            }
+            Inline::Tag(tag) => {
+                out.push_str(&format!(
+                    "<a class=\"tag\" data-tag=\"{0}\">#{0}</a>",
+                    escape_html(tag)
+                ));
+            }
+            Inline::BlockRef(id) => {
+                let text = blocks.get(id).map(String::as_str).unwrap_or(id);
+                out.push_str(&format!(
+                    "<span class=\"block-ref\" data-block=\"{}\">{}</span>",
+                    escape_html(id),
+                    escape_html(text)
+                ));
+            }
+            Inline::Math(tex) => out.push_str(&math_html(tex, false)),
+            Inline::DisplayMath(tex) => out.push_str(&math_html(tex, true)),
+            Inline::FootnoteRef(label) => {
+                out.push_str(&format!(
+                    "<sup><a href=\"#fn-{0}\">{0}</a></sup>",
+                    escape_html(label)
+                ));
+            }
+            Inline::Link { href, label } => {
+                out.push_str(&format!("<a href=\"{}\">", escape_html(href)));
+                out.push_str(&to_html(label, blocks));
+                out.push_str("</a>");
+            }
+            // A soft break, as CommonMark renders it
+            Inline::LineBreak => out.push('\n'),
+        }
+    }
+    out
+}
+
+fn math_html(tex: &str, display: bool) -> String {
+    match super::math::render_math(tex, display) {
+        Ok(mathml) => mathml,
+        Err(err) => format!(
+            "<span class=\"math-error\" title=\"{}\">{}</span>",
+            escape_html(&err),
+            escape_html(tex)
+        ),
+    }
+}
+
+fn wrap(out: &mut String, tag: &str, children: &[Inline], blocks: &HashMap<String, String>) {
+    out.push_str(&format!("<{}>", tag));
+    out.push_str(&to_html(children, blocks));
+    out.push_str(&format!("</{}>", tag));
+}
+
+/// Escape HTML special characters
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Inline {
+        Inline::Text(s.to_string())
+    }
+
+    fn tags(s: &str) -> Vec<String> {
+        collect_tags(&parse_inline(s))
+    }
+
+    #[test]
+    fn plain_text_stays_one_node() {
+        assert_eq!(parse_inline("just words"), vec![text("just words")]);
+        assert_eq!(parse_inline(""), vec![]);
+    }
+
+    #[test]
+    fn page_refs_with_and_without_alias() {
+        assert_eq!(
+            parse_inline("see [[Rust Book]] and [[Rust Book|the book]]"),
+            vec![
+                text("see "),
+                Inline::PageRef { page: "Rust Book".into(), label: None },
+                text(" and "),
+                Inline::PageRef { page: "Rust Book".into(), label: Some("the book".into()) },
+            ]
+        );
+        assert_eq!(parse_inline("[[ ]]"), vec![text("[[ ]]")]);
+    }
+
+    #[test]
+    fn tags_in_every_form() {
+        assert_eq!(tags("#rust and #[[multi word tag]]"), vec!["rust", "multi word tag"]);
+        assert_eq!(tags("#café #日本語"), vec!["café", "日本語"]);
+        assert_eq!(tags("#area/project-x"), vec!["area/project-x"]);
+        assert_eq!(tags("(#inside)"), vec!["inside"]);
+    }
+
+    #[test]
+    fn tags_need_a_word_boundary() {
+        assert!(tags("issue#42").is_empty());
+        assert!(tags("# heading-like").is_empty());
+        assert!(tags("`#not-a-tag`").is_empty());
+    }
+
+    #[test]
+    fn tags_inside_formatting_and_links() {
+        assert_eq!(tags("**bold #one** and [label #two](https://example.com)"), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn block_refs_and_footnotes() {
+        assert_eq!(
+            parse_inline("((abc-123)) note[^1]"),
+            vec![Inline::BlockRef("abc-123".into()), text(" note"), Inline::FootnoteRef("1".into())]
+        );
+        assert_eq!(parse_inline("((not an id))"), vec![text("((not an id))")]);
+    }
+
+    #[test]
+    fn emphasis_and_snake_case() {
+        assert_eq!(
+            parse_inline("**bold** _em_ snake_case_name"),
+            vec![
+                Inline::Strong(vec![text("bold")]),
+                text(" "),
+                Inline::Emphasis(vec![text("em")]),
+                text(" snake_case_name"),
+            ]
+        );
+        assert_eq!(parse_inline("a * b * c"), vec![text("a * b * c")]);
+    }
+
+    #[test]
+    fn code_spans_are_verbatim() {
+        assert_eq!(parse_inline("`**x**`"), vec![Inline::Code("**x**".into())]);
+        assert_eq!(parse_inline("`` a ` b ``"), vec![Inline::Code("a ` b".into())]);
+    }
+
+    #[test]
+    fn urls_drop_trailing_punctuation() {
+        assert_eq!(
+            parse_inline("go to https://example.com/a."),
+            vec![
+                text("go to "),
+                Inline::Link { href: "https://example.com/a".into(), label: vec![text("https://example.com/a")] },
+                text("."),
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_and_display_math() {
+        assert_eq!(
+            parse_inline("where $x^2$ and $$\\sum_i x_i$$"),
+            vec![text("where "), Inline::Math("x^2".into()), text(" and "), Inline::DisplayMath("\\sum_i x_i".into())]
+        );
+        assert_eq!(parse_inline("$$ $$"), vec![text("$$ $$")]);
+    }
+
+    #[test]
+    fn prices_are_not_math() {
+        assert_eq!(parse_inline("costs $5 and $6"), vec![text("costs $5 and $6")]);
+        assert_eq!(parse_inline("$5 to $10 range"), vec![text("$5 to $10 range")]);
+        assert_eq!(parse_inline("$ x$"), vec![text("$ x$")]);
+        assert_eq!(parse_inline("$x $"), vec![text("$x $")]);
+    }
+
+    #[test]
+    fn escaped_dollars() {
+        assert_eq!(parse_inline(r"\$5 and \$x\$"), vec![text("$5 and $x$")]);
+        assert_eq!(parse_inline(r"$a \$ b$"), vec![Inline::Math(r"a \$ b".into())]);
+    }
+
+    #[test]
+    fn backslash_escapes_punctuation() {
+        assert_eq!(parse_inline(r"\*not em\* \#no"), vec![text("*not em* #no")]);
+    }
+
+    #[test]
+    fn properties_split_from_body() {
+        let (props, body) = split_properties("title\ntype:: book\n```\nx:: y\n```");
+        assert_eq!(props, vec![("type".to_string(), "book".to_string())]);
+        assert_eq!(body, "title\n```\nx:: y\n```");
+        assert_eq!(set_property("a\ntype:: x", "type", "y"), "a\ntype:: y");
+    }
+}
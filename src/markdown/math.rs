@@ -0,0 +1,241 @@
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+
+/// Render LaTeX to a MathML string.
+/// Errors carry a short message for display next to the source.
+pub fn render_math(tex: &str, display: bool) -> Result<String, String> {
+    let style = if display { DisplayStyle::Block } else { DisplayStyle::Inline };
+
+    check_balanced(tex)?;
+    latex_to_mathml(tex, style)
+        .map(|mathml| sanitize_mathml(&mathml))
+        .map_err(|e| e.to_string())
+}
+
+/// MathML elements the renderer may emit
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "math", "semantics", "annotation", "mrow", "mi", "mn", "mo", "ms", "mtext", "mspace", "msup",
+    "msub", "msubsup", "mfrac", "msqrt", "mroot", "mover", "munder", "munderover", "mtable", "mtr",
+    "mtd", "mstyle", "mpadded", "mphantom", "menclose", "mfenced", "merror",
+];
+
+/// Presentation attributes kept on allowed elements; everything else
+/// (event handlers, `href`, `style`) is dropped
+const ALLOWED_ATTRIBUTES: &[&str] = &[
+    "xmlns", "display", "displaystyle", "scriptlevel", "mathvariant", "stretchy", "fence",
+    "separator", "separators", "form", "lspace", "rspace", "accent", "accentunder", "largeop",
+    "movablelimits", "symmetric", "minsize", "maxsize", "linethickness", "columnalign",
+    "rowspacing", "columnspacing", "width", "height", "depth", "notation", "open", "close",
+    "encoding",
+];
+
+/// Make converter output safe for `dangerous_inner_html`.
+/// Text such as `\text{<img onerror=...>}` reaches the output verbatim, so
+/// anything that is not an allowed MathML tag is escaped as text.
+pub fn sanitize_mathml(mathml: &str) -> String {
+    let mut out = String::with_capacity(mathml.len());
+    let mut rest = mathml;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => match parse_tag(rest) {
+                Some((tag, consumed)) => {
+                    out.push_str(&tag);
+                    rest = &rest[consumed..];
+                }
+                None => {
+                    out.push_str("&lt;");
+                    rest = &rest[1..];
+                }
+            },
+            '>' => {
+                out.push_str("&gt;");
+                rest = &rest[1..];
+            }
+            '&' => {
+                let entity = rest[1..]
+                    .find(';')
+                    .map(|end| &rest[1..1 + end])
+                    .filter(|name| is_entity(name));
+                match entity {
+                    Some(name) => {
+                        out.push('&');
+                        out.push_str(name);
+                        out.push(';');
+                        rest = &rest[name.len() + 2..];
+                    }
+                    None => {
+                        out.push_str("&amp;");
+                        rest = &rest[1..];
+                    }
+                }
+            }
+            _ => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    out
+}
+
+/// `name`, `#123` or `#x1F`
+fn is_entity(name: &str) -> bool {
+    match name.strip_prefix('#') {
+        Some(code) => match code.strip_prefix(['x', 'X']) {
+            Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+            None => !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()),
+        },
+        None => !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()),
+    }
+}
+
+/// Parse an allowed opening, closing or self-closing tag at the start of
+/// `rest`, rebuilt with only allowed attributes
+fn parse_tag(rest: &str) -> Option<(String, usize)> {
+    let end = rest.find('>')?;
+    let inner = &rest[1..end];
+    if inner.contains('<') {
+        return None;
+    }
+
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+    let (inner, self_closing) = match inner.strip_suffix('/') {
+        Some(inner) => (inner, true),
+        None => (inner, false),
+    };
+    let name_len = inner.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(inner.len());
+    let name = &inner[..name_len];
+    if !ALLOWED_ELEMENTS.contains(&name) {
+        return None;
+    }
+    if closing {
+        return inner[name_len..].trim().is_empty().then(|| (format!("</{}>", name), end + 1));
+    }
+
+    let mut tag = format!("<{}", name);
+    for (key, value) in parse_attributes(&inner[name_len..])? {
+        if ALLOWED_ATTRIBUTES.contains(&key) {
+            tag.push_str(&format!(" {}=\"{}\"", key, super::inline::escape_html(value)));
+        }
+    }
+    tag.push_str(if self_closing { "/>" } else { ">" });
+    Some((tag, end + 1))
+}
+
+/// `key="value"` pairs; `None` when the attribute list is malformed
+fn parse_attributes(mut text: &str) -> Option<Vec<(&str, &str)>> {
+    let mut attributes = Vec::new();
+    loop {
+        text = text.trim_start();
+        if text.is_empty() {
+            return Some(attributes);
+        }
+        let eq = text.find('=')?;
+        let key = text[..eq].trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == ':') {
+            return None;
+        }
+        let value = text[eq + 1..].trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let close = value[1..].find(quote)?;
+        attributes.push((key, &value[1..1 + close]));
+        text = &value[close + 2..];
+    }
+}
+
+/// Reject unbalanced braces and environments up front; the converter
+/// otherwise tends to produce truncated output instead of an error.
+fn check_balanced(tex: &str) -> Result<(), String> {
+    let mut depth = 0i32;
+    let mut environments: Vec<&str> = Vec::new();
+    let mut chars = tex.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let rest = &tex[i + 1..];
+                if let Some(name) = env_name(rest, "begin") {
+                    environments.push(name);
+                } else if let Some(name) = env_name(rest, "end") {
+                    match environments.pop() {
+                        Some(open) if open == name => {}
+                        Some(open) => return Err(format!("\\end{{{}}} does not match \\begin{{{}}}", name, open)),
+                        None => return Err(format!("\\end{{{}}} without \\begin", name)),
+                    }
+                }
+                // Skip the escaped character so `\{` is not counted
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err("Unexpected '}'".to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if depth > 0 {
+        return Err("Missing '}'".to_string());
+    }
+    if let Some(open) = environments.pop() {
+        return Err(format!("Missing \\end{{{}}}", open));
+    }
+    Ok(())
+}
+
+fn env_name<'a>(rest: &'a str, command: &str) -> Option<&'a str> {
+    let after = rest.strip_prefix(command)?.trim_start().strip_prefix('{')?;
+    let end = after.find('}')?;
+    Some(&after[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mathml_passes_through() {
+        let mathml = r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="block"><msup><mi>x</mi><mn>2</mn></msup><mo>&lt;</mo><mspace width="1em"/></math>"#;
+        assert_eq!(sanitize_mathml(mathml), mathml);
+    }
+
+    #[test]
+    fn text_content_is_escaped() {
+        assert_eq!(
+            sanitize_mathml("<mtext><img src=x onerror=alert(1)></mtext>"),
+            "<mtext>&lt;img src=x onerror=alert(1)&gt;</mtext>"
+        );
+        assert_eq!(
+            sanitize_mathml("<mtext></mtext><script>x</script>"),
+            "<mtext></mtext>&lt;script&gt;x&lt;/script&gt;"
+        );
+        assert_eq!(sanitize_mathml("<mi>a & b &amp; &#60;</mi>"), "<mi>a &amp; b &amp; &#60;</mi>");
+    }
+
+    #[test]
+    fn unsafe_attributes_are_dropped() {
+        assert_eq!(
+            sanitize_mathml(r#"<mi mathvariant="bold" onclick="alert(1)" href='javascript:x'>a</mi>"#),
+            r#"<mi mathvariant="bold">a</mi>"#
+        );
+        assert_eq!(sanitize_mathml("<mi onclick=alert(1)>a</mi>"), "&lt;mi onclick=alert(1)&gt;a</mi>");
+    }
+
+    #[test]
+    fn unbalanced_input_is_rejected() {
+        assert!(check_balanced(r"\frac{a}{b}").is_ok());
+        assert!(check_balanced(r"\{ x \}").is_ok());
+        assert_eq!(check_balanced(r"\frac{a}{b").unwrap_err(), "Missing '}'");
+        assert_eq!(check_balanced("a}").unwrap_err(), "Unexpected '}'");
+        assert!(check_balanced(r"\begin{matrix} a \end{matrix}").is_ok());
+        assert!(check_balanced(r"\begin{matrix} a \end{cases}").is_err());
+        assert!(check_balanced(r"\begin{matrix} a").is_err());
+    }
+}
@@ -0,0 +1,10 @@
+// Markdown parsing for block content
+// Produces a typed AST that components render as Dioxus nodes
+
+pub mod inline;
+pub mod block;
+pub mod highlight;
+pub mod math;
+
+pub use inline::{collect_tags, parse_inline, set_property, split_properties, Inline};
+pub use block::{parse_blocks, BlockNode, ColumnAlign, ListItem};
@@ -0,0 +1,436 @@
+use std::collections::HashSet;
+use crate::markdown::set_property;
+use crate::store::{AppState, Block};
+use crate::tasks::{set_marker, TaskMarker};
+
+/// Block ids of a page in document order: each block followed by its descendants
+pub fn document_order(state: &AppState, page_id: &str) -> Vec<String> {
+    fn walk(state: &AppState, ids: &[String], order: &mut Vec<String>) {
+        for id in ids {
+            if let Some(block) = state.blocks.get(id) {
+                order.push(id.clone());
+                walk(state, &block.children, order);
+            }
+        }
+    }
+
+    let mut order = Vec::new();
+    if let Some(page) = state.pages.get(page_id) {
+        walk(state, &page.blocks, &mut order);
+    }
+    order
+}
+
+/// Ancestors of a block, outermost first, not including the block itself
+pub fn ancestors(state: &AppState, id: &str) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut parent = state.blocks.get(id).and_then(|b| b.parent_id.clone());
+    while let Some(pid) = parent {
+        parent = state.blocks.get(&pid).and_then(|b| b.parent_id.clone());
+        chain.push(pid);
+    }
+    chain.reverse();
+    chain
+}
+
+/// Short plain-text label for a block, for breadcrumbs
+pub fn block_label(block: &Block) -> String {
+    let (_, body) = crate::markdown::split_properties(&block.content);
+    let first = body.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+    let text = crate::markdown::inline::plain_text(&crate::markdown::parse_inline(first.trim()));
+    if text.chars().count() > 40 {
+        format!("{}…", text.chars().take(40).collect::<String>().trim_end())
+    } else {
+        text
+    }
+}
+
+/// Block property holding the collapse state: `collapsed:: true`
+pub const COLLAPSED_PROPERTY: &str = "collapsed";
+
+pub fn is_collapsed(block: &Block) -> bool {
+    block.properties.get(COLLAPSED_PROPERTY).map(|v| v == "true").unwrap_or(false)
+}
+
+/// Collapse or expand a block; blocks without children stay expanded
+pub fn set_collapsed(state: &mut AppState, id: &str, collapsed: bool) {
+    if let Some(block) = state.blocks.get_mut(id) {
+        if collapsed && !block.children.is_empty() {
+            block.properties.insert(COLLAPSED_PROPERTY.to_string(), "true".to_string());
+        } else {
+            block.properties.remove(COLLAPSED_PROPERTY);
+        }
+    }
+}
+
+/// Like `document_order`, skipping the descendants of collapsed blocks
+pub fn visible_order(state: &AppState, page_id: &str) -> Vec<String> {
+    fn walk(state: &AppState, ids: &[String], order: &mut Vec<String>) {
+        for id in ids {
+            if let Some(block) = state.blocks.get(id) {
+                order.push(id.clone());
+                if !is_collapsed(block) {
+                    walk(state, &block.children, order);
+                }
+            }
+        }
+    }
+
+    let mut order = Vec::new();
+    if let Some(page) = state.pages.get(page_id) {
+        walk(state, &page.blocks, &mut order);
+    }
+    order
+}
+
+/// Show `level` levels of a page's outline: blocks above that depth are
+/// expanded, the rest collapsed. `None` expands everything.
+pub fn expand_to_level(state: &mut AppState, page_id: &str, level: Option<usize>) {
+    fn walk(state: &mut AppState, ids: Vec<String>, depth: usize, level: Option<usize>) {
+        for id in ids {
+            let collapsed = level.map(|l| depth + 1 >= l).unwrap_or(false);
+            set_collapsed(state, &id, collapsed);
+            let children = state.blocks.get(&id).map(|b| b.children.clone()).unwrap_or_default();
+            walk(state, children, depth + 1, level);
+        }
+    }
+
+    let roots = state.pages.get(page_id).map(|p| p.blocks.clone()).unwrap_or_default();
+    walk(state, roots, 0, level);
+}
+
+/// Blocks between `anchor` and `focus` inclusive, across nesting levels
+pub fn selection_range(order: &[String], anchor: &str, focus: &str) -> Vec<String> {
+    let a = order.iter().position(|id| id == anchor);
+    let f = order.iter().position(|id| id == focus);
+    match (a, f) {
+        (Some(a), Some(f)) => order[a.min(f)..=a.max(f)].to_vec(),
+        (None, Some(f)) => vec![order[f].clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Block before or after `id` in document order
+pub fn adjacent_block(order: &[String], id: &str, forward: bool) -> Option<String> {
+    let i = order.iter().position(|b| b == id)?;
+    let j = if forward { i.checked_add(1)? } else { i.checked_sub(1)? };
+    order.get(j).cloned()
+}
+
+/// Selected blocks whose ancestors are not selected, in document order.
+/// Bulk operations act on these so a subtree moves as one.
+pub fn selection_roots(state: &AppState, selected: &[String]) -> Vec<String> {
+    let set: HashSet<&str> = selected.iter().map(String::as_str).collect();
+    selected
+        .iter()
+        .filter(|id| {
+            let mut parent = state.blocks.get(id.as_str()).and_then(|b| b.parent_id.as_deref());
+            while let Some(pid) = parent {
+                if set.contains(pid) {
+                    return false;
+                }
+                parent = state.blocks.get(pid).and_then(|b| b.parent_id.as_deref());
+            }
+            true
+        })
+        .cloned()
+        .collect()
+}
+
+/// The list holding `id`: its parent's children, or the page's top-level blocks
+fn siblings_mut<'a>(state: &'a mut AppState, page_id: &str, id: &str) -> Option<&'a mut Vec<String>> {
+    match state.blocks.get(id)?.parent_id.clone() {
+        Some(pid) => state.blocks.get_mut(&pid).map(|b| &mut b.children),
+        None => state.pages.get_mut(page_id).map(|p| &mut p.blocks),
+    }
+}
+
+/// Take a block (with its subtree) out of its sibling list
+fn detach(state: &mut AppState, page_id: &str, id: &str) {
+    if let Some(siblings) = siblings_mut(state, page_id, id) {
+        siblings.retain(|s| s != id);
+    }
+}
+
+/// Make a block the last child of its previous sibling
+pub fn indent_block(state: &mut AppState, page_id: &str, id: &str) -> bool {
+    let previous = match siblings_mut(state, page_id, id) {
+        Some(siblings) => match siblings.iter().position(|s| s == id) {
+            Some(i) if i > 0 => siblings[i - 1].clone(),
+            _ => return false,
+        },
+        None => return false,
+    };
+
+    detach(state, page_id, id);
+    if let Some(block) = state.blocks.get_mut(id) {
+        block.parent_id = Some(previous.clone());
+    }
+    if let Some(parent) = state.blocks.get_mut(&previous) {
+        parent.children.push(id.to_string());
+    }
+    // Keep the indented block in view
+    set_collapsed(state, &previous, false);
+    true
+}
+
+/// Move a block out of its parent, right after the parent
+pub fn outdent_block(state: &mut AppState, page_id: &str, id: &str) -> bool {
+    let parent_id = match state.blocks.get(id).and_then(|b| b.parent_id.clone()) {
+        Some(pid) => pid,
+        None => return false,
+    };
+    let grandparent = state.blocks.get(&parent_id).and_then(|b| b.parent_id.clone());
+
+    detach(state, page_id, id);
+    if let Some(block) = state.blocks.get_mut(id) {
+        block.parent_id = grandparent;
+    }
+    if let Some(siblings) = siblings_mut(state, page_id, &parent_id) {
+        let position = siblings.iter().position(|s| *s == parent_id).map(|i| i + 1).unwrap_or(siblings.len());
+        siblings.insert(position, id.to_string());
+    }
+    true
+}
+
+/// Indent blocks in order; each one lands under the block before it
+pub fn indent_blocks(state: &mut AppState, page_id: &str, ids: &[String]) {
+    for id in ids {
+        indent_block(state, page_id, id);
+    }
+}
+
+/// Outdent blocks, last first, so they keep their order after the parent
+pub fn outdent_blocks(state: &mut AppState, page_id: &str, ids: &[String]) {
+    for id in ids.iter().rev() {
+        outdent_block(state, page_id, id);
+    }
+}
+
+/// Delete blocks and their subtrees
+pub fn remove_blocks(state: &mut AppState, page_id: &str, ids: &[String]) {
+    for id in ids {
+        detach(state, page_id, id);
+        state.delete_block(id);
+    }
+    if let Some(page) = state.pages.get_mut(page_id) {
+        page.updated_at = chrono::Utc::now();
+    }
+}
+
+/// Move blocks and their subtrees to the end of another page
+pub fn move_blocks(state: &mut AppState, from_page: &str, to_page: &str, ids: &[String]) -> bool {
+    if from_page == to_page || !state.pages.contains_key(to_page) {
+        return false;
+    }
+    for id in ids {
+        detach(state, from_page, id);
+        if let Some(block) = state.blocks.get_mut(id) {
+            block.parent_id = None;
+        }
+    }
+    let now = chrono::Utc::now();
+    if let Some(page) = state.pages.get_mut(to_page) {
+        page.blocks.extend(ids.iter().cloned());
+        page.updated_at = now;
+    }
+    if let Some(page) = state.pages.get_mut(from_page) {
+        page.updated_at = now;
+    }
+    true
+}
+
+/// Blocks and their subtrees as a `- item` markdown outline
+pub fn blocks_to_markdown(state: &AppState, ids: &[String]) -> String {
+    fn write(state: &AppState, id: &str, depth: usize, out: &mut Vec<String>) {
+        let block = match state.blocks.get(id) {
+            Some(block) => block,
+            None => return,
+        };
+        let indent = "  ".repeat(depth);
+        let mut lines = block.content.lines();
+        out.push(format!("{}- {}", indent, lines.next().unwrap_or_default()));
+        // Continuation lines line up with the text after the bullet
+        for line in lines {
+            out.push(format!("{}  {}", indent, line));
+        }
+        for child in &block.children {
+            write(state, child, depth + 1, out);
+        }
+    }
+
+    let mut out = Vec::new();
+    for id in ids {
+        write(state, id, 0, &mut out);
+    }
+    out.join("\n")
+}
+
+/// Rewrite the content of each block with `edit`
+pub fn edit_blocks(state: &mut AppState, ids: &[String], edit: impl Fn(&str) -> String) {
+    let now = chrono::Utc::now();
+    for id in ids {
+        if let Some(block) = state.blocks.get_mut(id) {
+            block.content = edit(&block.content);
+            block.updated_at = now;
+        }
+    }
+}
+
+/// Turn blocks into TODO tasks
+pub fn mark_todo(state: &mut AppState, ids: &[String]) {
+    edit_blocks(state, ids, |content| set_marker(content, TaskMarker::Todo));
+}
+
+/// Set a `key:: value` property on blocks
+pub fn set_blocks_property(state: &mut AppState, ids: &[String], key: &str, value: &str) {
+    edit_blocks(state, ids, |content| set_property(content, key, value));
+}
+
+/// Markdown headings among a page's blocks as `(block id, level, text)`,
+/// in document order
+pub fn table_of_contents(state: &AppState, page_id: &str) -> Vec<(String, u8, String)> {
+    document_order(state, page_id)
+        .into_iter()
+        .filter_map(|id| {
+            let block = state.blocks.get(&id)?;
+            let (_, body) = crate::markdown::split_properties(&block.content);
+            let line = body.lines().next()?.trim_start();
+            let level = line.chars().take_while(|c| *c == '#').count();
+            let text = line[level..].strip_prefix(' ')?.trim();
+            if level == 0 || level > 6 || text.is_empty() {
+                return None;
+            }
+            let text = crate::markdown::inline::plain_text(&crate::markdown::parse_inline(text));
+            Some((id, level as u8, text))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A page holding `- a` (with child `b`), `c` and `d`
+    fn sample() -> (AppState, String, Vec<String>) {
+        let mut state = AppState::default();
+        let page = state.create_page("Outline");
+        let ids = state.insert_outline(&page, "- a\n  - b\n- c\n- d");
+        (state, page, ids)
+    }
+
+    fn outline(state: &AppState, page_id: &str) -> String {
+        blocks_to_markdown(state, &state.pages[page_id].blocks)
+    }
+
+    #[test]
+    fn ranges_span_nesting_levels_in_either_direction() {
+        let (state, page, ids) = sample();
+        let order = document_order(&state, &page);
+        assert_eq!(order, ids);
+        assert_eq!(selection_range(&order, &ids[2], &ids[0]), &ids[..3]);
+        assert_eq!(selection_range(&order, &ids[1], &ids[3]), &ids[1..]);
+        // A missing anchor selects just the focus
+        assert_eq!(selection_range(&order, "gone", &ids[1]), [ids[1].clone()]);
+        assert!(selection_range(&order, &ids[0], "gone").is_empty());
+    }
+
+    #[test]
+    fn roots_drop_blocks_under_a_selected_ancestor() {
+        let (state, _, ids) = sample();
+        let selected = vec![ids[0].clone(), ids[1].clone(), ids[3].clone()];
+        assert_eq!(selection_roots(&state, &selected), [ids[0].clone(), ids[3].clone()]);
+    }
+
+    #[test]
+    fn indent_and_outdent_keep_block_order() {
+        let (mut state, page, ids) = sample();
+        // The first block has nothing to indent under
+        assert!(!indent_block(&mut state, &page, &ids[0]));
+
+        indent_blocks(&mut state, &page, &ids[2..]);
+        assert_eq!(outline(&state, &page), "- a\n  - b\n  - c\n  - d");
+
+        outdent_blocks(&mut state, &page, &ids[1..]);
+        assert_eq!(outline(&state, &page), "- a\n- b\n- c\n- d");
+        assert!(state.blocks[&ids[1]].parent_id.is_none());
+        assert!(!outdent_block(&mut state, &page, &ids[1]));
+    }
+
+    #[test]
+    fn collapsed_blocks_hide_their_descendants() {
+        let mut state = AppState::default();
+        let page = state.create_page("Deep");
+        let ids = state.insert_outline(&page, "- a\n  - b\n    - c\n- d");
+
+        set_collapsed(&mut state, &ids[1], true);
+        assert_eq!(visible_order(&state, &page), [&ids[0], &ids[1], &ids[3]].map(String::clone));
+        set_collapsed(&mut state, &ids[0], true);
+        assert_eq!(visible_order(&state, &page), [&ids[0], &ids[3]].map(String::clone));
+        // Leaves never collapse
+        set_collapsed(&mut state, &ids[3], true);
+        assert!(!is_collapsed(&state.blocks[&ids[3]]));
+        assert!(visible_order(&state, "missing").is_empty());
+    }
+
+    #[test]
+    fn expanding_to_a_level_folds_deeper_blocks() {
+        let mut state = AppState::default();
+        let page = state.create_page("Deep");
+        let ids = state.insert_outline(&page, "- a\n  - b\n    - c\n      - d\n- e");
+        let collapsed = |state: &AppState| -> Vec<bool> {
+            ids.iter().map(|id| is_collapsed(&state.blocks[id])).collect()
+        };
+
+        expand_to_level(&mut state, &page, Some(1));
+        assert_eq!(collapsed(&state), [true, true, true, false, false]);
+        assert_eq!(visible_order(&state, &page), [&ids[0], &ids[4]].map(String::clone));
+
+        expand_to_level(&mut state, &page, Some(2));
+        assert_eq!(collapsed(&state), [false, true, true, false, false]);
+        assert_eq!(visible_order(&state, &page).len(), 3);
+
+        expand_to_level(&mut state, &page, None);
+        assert_eq!(collapsed(&state), [false; 5]);
+        assert_eq!(visible_order(&state, &page), ids);
+    }
+
+    #[test]
+    fn indenting_expands_the_new_parent() {
+        let (mut state, page, ids) = sample();
+        set_collapsed(&mut state, &ids[0], true);
+        indent_block(&mut state, &page, &ids[2]);
+        assert!(!is_collapsed(&state.blocks[&ids[0]]));
+    }
+
+    #[test]
+    fn moved_blocks_take_their_subtree() {
+        let (mut state, page, ids) = sample();
+        let other = state.create_page("Other");
+        state.insert_outline(&other, "- x");
+
+        assert!(!move_blocks(&mut state, &page, &page, &ids[..1]));
+        assert!(!move_blocks(&mut state, &page, "missing", &ids[..1]));
+        assert!(move_blocks(&mut state, &page, &other, &[ids[0].clone(), ids[3].clone()]));
+        assert_eq!(outline(&state, &page), "- c");
+        assert_eq!(outline(&state, &other), "- x\n- a\n  - b\n- d");
+        assert_eq!(state.find_page_for_block(&ids[1]).map(|p| p.id.clone()), Some(other));
+    }
+
+    #[test]
+    fn removed_blocks_take_their_subtree() {
+        let (mut state, page, ids) = sample();
+        remove_blocks(&mut state, &page, &ids[..1]);
+        assert_eq!(outline(&state, &page), "- c\n- d");
+        assert!(!state.blocks.contains_key(&ids[0]));
+        assert!(!state.blocks.contains_key(&ids[1]));
+    }
+
+    #[test]
+    fn markdown_aligns_continuation_lines() {
+        let (mut state, page, ids) = sample();
+        state.blocks.get_mut(&ids[1]).unwrap().content = "b\nmore".to_string();
+        assert_eq!(outline(&state, &page), "- a\n  - b\n    more\n- c\n- d");
+        assert_eq!(blocks_to_markdown(&state, &ids[1..2]), "- b\n  more");
+    }
+}
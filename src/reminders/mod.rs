@@ -0,0 +1,496 @@
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use crate::store::AppState;
+use crate::tasks::{parse_planning, task_marker, task_title, Timestamp};
+
+/// How often the background scheduler scans for due reminders
+pub const REMINDER_INTERVAL_MS: u32 = 30_000;
+
+/// Source of the current time; swapped for `ManualClock` to drive the
+/// scheduler deterministically
+pub trait Clock {
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// Local wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        crate::journal::now()
+    }
+}
+
+/// Clock that only moves when told to
+#[derive(Debug, Clone)]
+pub struct ManualClock(Cell<NaiveDateTime>);
+
+impl ManualClock {
+    pub fn new(now: NaiveDateTime) -> Self {
+        Self(Cell::new(now))
+    }
+
+    pub fn set(&self, now: NaiveDateTime) {
+        self.0.set(now);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.0.set(self.0.get() + by);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> NaiveDateTime {
+        self.0.get()
+    }
+}
+
+/// Which planning date a reminder comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReminderKind {
+    Scheduled,
+    Deadline,
+}
+
+impl ReminderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReminderKind::Scheduled => "scheduled",
+            ReminderKind::Deadline => "deadline",
+        }
+    }
+}
+
+/// A planned date on an open block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub block_id: String,
+    pub page_id: Option<String>,
+    pub kind: ReminderKind,
+    /// When the date falls due; untimed dates use `ReminderConfig::default_time`
+    pub due: NaiveDateTime,
+    /// Whether the timestamp carried an explicit time
+    pub timed: bool,
+    pub text: String,
+}
+
+impl Reminder {
+    /// Identifies one occurrence, so a rescheduled or repeated task fires again
+    pub fn key(&self) -> String {
+        format!("{}:{}:{}", self.block_id, self.kind.as_str(), self.due.format("%Y-%m-%dT%H:%M"))
+    }
+
+    /// Short due description for the tray, e.g. `Deadline Oct 20 14:00`
+    pub fn due_label(&self) -> String {
+        let kind = match self.kind {
+            ReminderKind::Scheduled => "Scheduled",
+            ReminderKind::Deadline => "Deadline",
+        };
+        if self.timed {
+            format!("{} {}", kind, self.due.format("%b %-d %H:%M"))
+        } else {
+            format!("{} {}", kind, self.due.format("%b %-d"))
+        }
+    }
+
+    /// Notification title: the task text, or a generic one
+    pub fn title(&self) -> &str {
+        if self.text.is_empty() { "Reminder" } else { self.text.as_str() }
+    }
+}
+
+/// Reminder settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReminderConfig {
+    /// Time of day untimed scheduled/deadline dates fire at
+    pub default_time: NaiveTime,
+    /// How far ahead of a deadline to warn
+    pub deadline_lead_minutes: i64,
+    /// Also show browser/desktop notifications when permitted
+    pub browser_notifications: bool,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            default_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default(),
+            deadline_lead_minutes: 60,
+            browser_notifications: true,
+        }
+    }
+}
+
+/// Persisted scheduler bookkeeping
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReminderState {
+    /// Reminder keys that already fired
+    #[serde(default)]
+    pub notified: HashSet<String>,
+    /// Reminder keys held back until the given time
+    #[serde(default)]
+    pub snoozed: HashMap<String, NaiveDateTime>,
+}
+
+/// Collect reminders for every block with a planning date that is not done
+pub fn collect_reminders(state: &AppState, config: &ReminderConfig) -> Vec<Reminder> {
+    let mut reminders = Vec::new();
+
+    for block in state.blocks.values() {
+        if task_marker(&block.content).map(|m| m.is_closed()).unwrap_or(false) {
+            continue;
+        }
+
+        let planning = parse_planning(&block.content);
+        let dates = [
+            (ReminderKind::Scheduled, planning.scheduled),
+            (ReminderKind::Deadline, planning.deadline),
+        ];
+        for (kind, timestamp) in dates {
+            if let Some(timestamp) = timestamp {
+                reminders.push(reminder_for(state, &block.id, &block.content, kind, &timestamp, config));
+            }
+        }
+    }
+
+    reminders.sort_by(|a, b| a.due.cmp(&b.due));
+    reminders
+}
+
+fn reminder_for(
+    state: &AppState,
+    block_id: &str,
+    content: &str,
+    kind: ReminderKind,
+    timestamp: &Timestamp,
+    config: &ReminderConfig,
+) -> Reminder {
+    Reminder {
+        block_id: block_id.to_string(),
+        page_id: state.find_page_for_block(block_id).map(|p| p.id.clone()),
+        kind,
+        due: timestamp.date.and_time(timestamp.time.unwrap_or(config.default_time)),
+        timed: timestamp.time.is_some(),
+        text: task_title(content),
+    }
+}
+
+/// Open reminders whose date is before today
+pub fn overdue(reminders: &[Reminder], now: NaiveDateTime) -> Vec<Reminder> {
+    let today = now.date();
+    reminders.iter().filter(|r| r.due.date() < today).cloned().collect()
+}
+
+/// Whether a reminder should fire at `now`
+fn is_due(reminder: &Reminder, key: &str, state: &AppState, config: &ReminderConfig, now: NaiveDateTime) -> bool {
+    let fires_at = match reminder.kind {
+        ReminderKind::Scheduled => reminder.due,
+        ReminderKind::Deadline => reminder.due - Duration::minutes(config.deadline_lead_minutes),
+    };
+    if fires_at > now || state.reminders.notified.contains(key) {
+        return false;
+    }
+    match state.reminders.snoozed.get(key) {
+        // A snooze fires when it runs out, even if that is after midnight
+        Some(until) => *until <= now,
+        // Dates from earlier days are listed as overdue instead of flooding the tray
+        None => reminder.due.date() >= now.date(),
+    }
+}
+
+/// Scans planning dates and moves due reminders into the notification tray
+pub struct ReminderScheduler<C: Clock = SystemClock> {
+    clock: C,
+}
+
+impl<C: Clock> ReminderScheduler<C> {
+    pub fn new(clock: C) -> Self {
+        Self { clock }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Whether `tick` would fire a reminder or drop stale bookkeeping,
+    /// so the caller can skip taking a write lock on idle ticks
+    pub fn needs_tick(&self, state: &AppState) -> bool {
+        let now = self.clock.now();
+        let config = &state.reminder_config;
+        let reminders = collect_reminders(state, config);
+        let live: HashSet<String> = reminders.iter().map(|r| r.key()).collect();
+
+        state.reminders.notified.iter().any(|key| !live.contains(key))
+            || state.reminders.snoozed.keys().any(|key| !live.contains(key))
+            || reminders.iter().any(|r| is_due(r, &r.key(), state, config, now))
+    }
+
+    /// Fire reminders that became due since the last tick.
+    /// Newly fired reminders are added to `state.notifications` and returned.
+    pub fn tick(&self, state: &mut AppState) -> Vec<Reminder> {
+        let now = self.clock.now();
+        let config = state.reminder_config.clone();
+        let reminders = collect_reminders(state, &config);
+
+        // Forget bookkeeping for occurrences that no longer exist
+        let live: HashSet<String> = reminders.iter().map(|r| r.key()).collect();
+        state.reminders.notified.retain(|key| live.contains(key));
+        state.reminders.snoozed.retain(|key, _| live.contains(key));
+
+        let mut fired = Vec::new();
+        for reminder in reminders {
+            let key = reminder.key();
+            if !is_due(&reminder, &key, state, &config, now) {
+                continue;
+            }
+
+            state.reminders.snoozed.remove(&key);
+            state.reminders.notified.insert(key.clone());
+            state.notifications.retain(|n| n.key() != key);
+            state.notifications.push(reminder.clone());
+            fired.push(reminder);
+        }
+
+        fired
+    }
+
+    /// Hide a reminder until `by` from now; it fires again on a later tick
+    pub fn snooze(&self, state: &mut AppState, key: &str, by: Duration) {
+        state.reminders.snoozed.insert(key.to_string(), self.clock.now() + by);
+        state.reminders.notified.remove(key);
+        state.notifications.retain(|n| n.key() != key);
+    }
+
+    /// Remove a reminder from the tray without firing it again
+    pub fn dismiss(&self, state: &mut AppState, key: &str) {
+        state.notifications.retain(|n| n.key() != key);
+    }
+
+    /// Open reminders from days before today
+    pub fn overdue(&self, state: &AppState) -> Vec<Reminder> {
+        overdue(&collect_reminders(state, &state.reminder_config), self.clock.now())
+    }
+}
+
+/// Notification permission, as far as the app is concerned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationPermission {
+    Granted,
+    Denied,
+    /// Not asked yet
+    Default,
+    /// The platform has no Notification API
+    Unsupported,
+}
+
+#[cfg(feature = "web")]
+pub fn notification_permission() -> NotificationPermission {
+    // Older browsers and some webviews have no Notification constructor
+    let supported = web_sys::window()
+        .map(|w| js_sys::Reflect::has(&w, &"Notification".into()).unwrap_or(false))
+        .unwrap_or(false);
+    if !supported {
+        return NotificationPermission::Unsupported;
+    }
+
+    match web_sys::Notification::permission() {
+        web_sys::NotificationPermission::Granted => NotificationPermission::Granted,
+        web_sys::NotificationPermission::Denied => NotificationPermission::Denied,
+        _ => NotificationPermission::Default,
+    }
+}
+
+/// The desktop build asks the OS directly; it decides whether to show them
+#[cfg(all(feature = "desktop", not(feature = "web")))]
+pub fn notification_permission() -> NotificationPermission {
+    NotificationPermission::Granted
+}
+
+#[cfg(not(any(feature = "web", feature = "desktop")))]
+pub fn notification_permission() -> NotificationPermission {
+    NotificationPermission::Unsupported
+}
+
+/// Ask the browser for notification permission
+#[cfg(feature = "web")]
+pub fn request_notification_permission() {
+    if notification_permission() == NotificationPermission::Default {
+        let _ = web_sys::Notification::request_permission();
+    }
+}
+
+#[cfg(not(feature = "web"))]
+pub fn request_notification_permission() {}
+
+/// Show a browser notification; returns false when not permitted so the
+/// caller can rely on the in-app tray alone
+#[cfg(feature = "web")]
+pub fn show_notification(reminder: &Reminder) -> bool {
+    if notification_permission() != NotificationPermission::Granted {
+        return false;
+    }
+
+    let options = web_sys::NotificationOptions::new();
+    options.set_body(&reminder.due_label());
+    options.set_tag(&reminder.key());
+    web_sys::Notification::new_with_options(reminder.title(), &options).is_ok()
+}
+
+/// Show a native desktop notification; returns false when the OS has no
+/// notification service, leaving the reminder in the tray only
+#[cfg(all(feature = "desktop", not(feature = "web")))]
+pub fn show_notification(reminder: &Reminder) -> bool {
+    notify_rust::Notification::new()
+        .appname("DioxusBrain")
+        .summary(reminder.title())
+        .body(&reminder.due_label())
+        .show()
+        .is_ok()
+}
+
+#[cfg(not(any(feature = "web", feature = "desktop")))]
+pub fn show_notification(_reminder: &Reminder) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    /// A state holding one block with `content`, and a scheduler stopped at `now`
+    fn setup(content: &str, now: NaiveDateTime) -> (AppState, String, ReminderScheduler<ManualClock>) {
+        let mut state = AppState::default();
+        let page_id = state.create_page("Tasks");
+        let block_id = state.create_block(None);
+        state.blocks.get_mut(&block_id).unwrap().content = content.to_string();
+        state.pages.get_mut(&page_id).unwrap().blocks.push(block_id.clone());
+        (state, block_id, ReminderScheduler::new(ManualClock::new(now)))
+    }
+
+    #[test]
+    fn fires_once_when_due() {
+        let (mut state, block_id, scheduler) = setup("TODO call back\nSCHEDULED: <2026-03-09 Mon 10:00>", at(9, 9, 59));
+
+        assert!(!scheduler.needs_tick(&state));
+        assert!(scheduler.tick(&mut state).is_empty());
+
+        scheduler.clock().set(at(9, 10, 0));
+        assert!(scheduler.needs_tick(&state));
+        let fired = scheduler.tick(&mut state);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].block_id, block_id);
+        assert_eq!(fired[0].text, "call back");
+        assert_eq!(state.notifications.len(), 1);
+
+        scheduler.clock().advance(Duration::minutes(1));
+        assert!(!scheduler.needs_tick(&state));
+        assert!(scheduler.tick(&mut state).is_empty());
+        assert_eq!(state.notifications.len(), 1);
+    }
+
+    #[test]
+    fn deadlines_fire_ahead_by_the_lead_time() {
+        let (mut state, _, scheduler) = setup("TODO report\nDEADLINE: <2026-03-09 Mon 12:00>", at(9, 10, 59));
+        state.reminder_config.deadline_lead_minutes = 60;
+
+        assert!(scheduler.tick(&mut state).is_empty());
+        scheduler.clock().set(at(9, 11, 0));
+        assert_eq!(scheduler.tick(&mut state).len(), 1);
+    }
+
+    #[test]
+    fn untimed_dates_use_the_default_time() {
+        let (mut state, _, scheduler) = setup("TODO water plants\nSCHEDULED: <2026-03-09 Mon>", at(9, 8, 0));
+        state.reminder_config.default_time = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+
+        assert!(scheduler.tick(&mut state).is_empty());
+        scheduler.clock().set(at(9, 9, 0));
+        assert_eq!(scheduler.tick(&mut state).len(), 1);
+    }
+
+    #[test]
+    fn closed_tasks_never_fire() {
+        let (mut state, _, scheduler) = setup("DONE call back\nSCHEDULED: <2026-03-09 Mon 10:00>", at(9, 10, 0));
+        assert!(!scheduler.needs_tick(&state));
+        assert!(scheduler.tick(&mut state).is_empty());
+    }
+
+    #[test]
+    fn snoozed_reminders_fire_again_later() {
+        let (mut state, _, scheduler) = setup("TODO stretch\nSCHEDULED: <2026-03-09 Mon 10:00>", at(9, 10, 0));
+        let key = scheduler.tick(&mut state)[0].key();
+
+        scheduler.snooze(&mut state, &key, Duration::minutes(10));
+        assert!(state.notifications.is_empty());
+
+        scheduler.clock().set(at(9, 10, 9));
+        assert!(!scheduler.needs_tick(&state));
+        assert!(scheduler.tick(&mut state).is_empty());
+
+        scheduler.clock().set(at(9, 10, 10));
+        assert_eq!(scheduler.tick(&mut state).len(), 1);
+        assert!(state.reminders.snoozed.is_empty());
+        assert_eq!(state.notifications.len(), 1);
+    }
+
+    #[test]
+    fn snoozing_past_midnight_still_fires() {
+        let (mut state, _, scheduler) = setup("TODO lock up\nSCHEDULED: <2026-03-09 Mon 23:50>", at(9, 23, 50));
+        let key = scheduler.tick(&mut state)[0].key();
+
+        scheduler.snooze(&mut state, &key, Duration::minutes(30));
+        scheduler.clock().set(at(10, 0, 19));
+        assert!(scheduler.tick(&mut state).is_empty());
+
+        scheduler.clock().set(at(10, 0, 20));
+        let fired = scheduler.tick(&mut state);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].key(), key);
+    }
+
+    #[test]
+    fn dismissed_reminders_stay_dismissed() {
+        let (mut state, _, scheduler) = setup("TODO stretch\nSCHEDULED: <2026-03-09 Mon 10:00>", at(9, 10, 0));
+        let key = scheduler.tick(&mut state)[0].key();
+
+        scheduler.dismiss(&mut state, &key);
+        assert!(state.notifications.is_empty());
+
+        scheduler.clock().advance(Duration::hours(1));
+        assert!(scheduler.tick(&mut state).is_empty());
+        assert!(state.notifications.is_empty());
+    }
+
+    #[test]
+    fn earlier_days_are_overdue_not_fired() {
+        let (mut state, block_id, scheduler) = setup("TODO file taxes\nSCHEDULED: <2026-03-08 Sun 10:00>", at(9, 8, 0));
+
+        assert!(!scheduler.needs_tick(&state));
+        assert!(scheduler.tick(&mut state).is_empty());
+
+        let overdue = scheduler.overdue(&state);
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue[0].block_id, block_id);
+    }
+
+    #[test]
+    fn stale_bookkeeping_is_pruned() {
+        let (mut state, block_id, scheduler) = setup("TODO stretch\nSCHEDULED: <2026-03-09 Mon 10:00>", at(9, 10, 0));
+        scheduler.tick(&mut state);
+        assert!(!scheduler.needs_tick(&state));
+
+        // Rescheduling makes the fired occurrence stale
+        state.blocks.get_mut(&block_id).unwrap().content = "TODO stretch\nSCHEDULED: <2026-03-10 Tue 10:00>".to_string();
+        assert!(scheduler.needs_tick(&state));
+        assert!(scheduler.tick(&mut state).is_empty());
+        assert!(state.reminders.notified.is_empty());
+        assert!(!scheduler.needs_tick(&state));
+    }
+}
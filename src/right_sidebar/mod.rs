@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::generate_id;
+
+/// What a sidebar card shows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SidebarTarget {
+    Page(String),
+    /// A block and its children
+    Block(String),
+}
+
+/// A page or block opened in the right sidebar stack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidebarItem {
+    pub id: String,
+    pub target: SidebarTarget,
+    pub collapsed: bool,
+}
+
+/// Panels about the current page that can be docked above the stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SidebarPanel {
+    Backlinks,
+    Outlinks,
+    PageGraph,
+    Contents,
+    Properties,
+}
+
+impl SidebarPanel {
+    pub const ALL: [SidebarPanel; 5] = [
+        SidebarPanel::Backlinks,
+        SidebarPanel::Outlinks,
+        SidebarPanel::PageGraph,
+        SidebarPanel::Contents,
+        SidebarPanel::Properties,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SidebarPanel::Backlinks => "Backlinks",
+            SidebarPanel::Outlinks => "Outlinks",
+            SidebarPanel::PageGraph => "Page graph",
+            SidebarPanel::Contents => "Contents",
+            SidebarPanel::Properties => "Properties",
+        }
+    }
+}
+
+/// The right sidebar: docked panels followed by a stack of opened pages
+/// and blocks, newest on top
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RightSidebar {
+    pub items: Vec<SidebarItem>,
+    pub panels: Vec<SidebarPanel>,
+}
+
+impl Default for RightSidebar {
+    fn default() -> Self {
+        Self { items: Vec::new(), panels: vec![SidebarPanel::Backlinks] }
+    }
+}
+
+impl RightSidebar {
+    /// Put a page or block on top of the stack, expanded. An already open
+    /// target moves to the top instead of opening twice.
+    pub fn open(&mut self, target: SidebarTarget) {
+        let item = match self.items.iter().position(|i| i.target == target) {
+            Some(i) => {
+                let mut item = self.items.remove(i);
+                item.collapsed = false;
+                item
+            }
+            None => SidebarItem { id: generate_id(), target, collapsed: false },
+        };
+        self.items.insert(0, item);
+    }
+
+    pub fn close(&mut self, item_id: &str) {
+        self.items.retain(|i| i.id != item_id);
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn toggle_collapsed(&mut self, item_id: &str) {
+        if let Some(item) = self.items.iter_mut().find(|i| i.id == item_id) {
+            item.collapsed = !item.collapsed;
+        }
+    }
+
+    pub fn is_docked(&self, panel: SidebarPanel) -> bool {
+        self.panels.contains(&panel)
+    }
+
+    /// Dock a panel, or undock it if it is already shown
+    pub fn toggle_panel(&mut self, panel: SidebarPanel) {
+        if self.is_docked(panel) {
+            self.panels.retain(|p| *p != panel);
+        } else {
+            // Keep panels in their canonical order
+            self.panels.push(panel);
+            self.panels.sort_by_key(|p| SidebarPanel::ALL.iter().position(|a| a == p));
+        }
+    }
+
+    /// Drop cards of pages and blocks that no longer exist
+    pub fn retain_targets(&mut self, exists: impl Fn(&SidebarTarget) -> bool) {
+        self.items.retain(|i| exists(&i.target));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(id: &str) -> SidebarTarget {
+        SidebarTarget::Page(id.to_string())
+    }
+
+    fn targets(sidebar: &RightSidebar) -> Vec<SidebarTarget> {
+        sidebar.items.iter().map(|i| i.target.clone()).collect()
+    }
+
+    #[test]
+    fn opening_puts_the_newest_on_top() {
+        let mut sidebar = RightSidebar::default();
+        sidebar.open(page("a"));
+        sidebar.open(SidebarTarget::Block("b".to_string()));
+        assert_eq!(targets(&sidebar), [SidebarTarget::Block("b".to_string()), page("a")]);
+    }
+
+    #[test]
+    fn reopening_moves_the_card_up_and_expands_it() {
+        let mut sidebar = RightSidebar::default();
+        for id in ["a", "b", "c"] {
+            sidebar.open(page(id));
+        }
+        let a = sidebar.items[2].id.clone();
+        sidebar.toggle_collapsed(&a);
+        assert!(sidebar.items[2].collapsed);
+
+        sidebar.open(page("a"));
+        assert_eq!(targets(&sidebar), [page("a"), page("c"), page("b")]);
+        // Same card, not a second one
+        assert_eq!(sidebar.items[0].id, a);
+        assert!(!sidebar.items[0].collapsed);
+    }
+
+    #[test]
+    fn panels_keep_their_canonical_order() {
+        let mut sidebar = RightSidebar::default();
+        sidebar.toggle_panel(SidebarPanel::Properties);
+        sidebar.toggle_panel(SidebarPanel::Outlinks);
+        sidebar.toggle_panel(SidebarPanel::Contents);
+        assert_eq!(sidebar.panels, [
+            SidebarPanel::Backlinks,
+            SidebarPanel::Outlinks,
+            SidebarPanel::Contents,
+            SidebarPanel::Properties,
+        ]);
+
+        sidebar.toggle_panel(SidebarPanel::Backlinks);
+        sidebar.toggle_panel(SidebarPanel::Contents);
+        assert_eq!(sidebar.panels, [SidebarPanel::Outlinks, SidebarPanel::Properties]);
+        assert!(!sidebar.is_docked(SidebarPanel::Contents));
+    }
+
+    #[test]
+    fn retaining_drops_missing_targets() {
+        let mut sidebar = RightSidebar::default();
+        for id in ["a", "gone", "c"] {
+            sidebar.open(page(id));
+        }
+        sidebar.retain_targets(|t| *t != page("gone"));
+        assert_eq!(targets(&sidebar), [page("c"), page("a")]);
+
+        let c = sidebar.items[0].id.clone();
+        sidebar.close(&c);
+        assert_eq!(targets(&sidebar), [page("a")]);
+        sidebar.clear();
+        assert!(sidebar.items.is_empty());
+    }
+}
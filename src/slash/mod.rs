@@ -0,0 +1,351 @@
+use regex::Regex;
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+use crate::store::AppState;
+use crate::tasks::{set_marker, TaskMarker};
+use crate::utils::fuzzy_score;
+
+/// Where a slash command was run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlashTarget {
+    pub page_id: String,
+    pub block_id: String,
+}
+
+/// What a slash command does to the block being edited
+#[derive(Clone)]
+pub enum SlashAction {
+    /// Replace the `/query` with text; `caret` is the caret offset within it
+    Insert { text: String, caret: Option<usize> },
+    /// Rewrite the whole block content (after removing the `/query`)
+    Transform(Rc<dyn Fn(&str) -> String>),
+    /// Remove the `/query`, save the block, then act on the store
+    Run(Rc<dyn Fn(&mut AppState, &SlashTarget)>),
+}
+
+impl fmt::Debug for SlashAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlashAction::Insert { text, caret } => f.debug_struct("Insert").field("text", text).field("caret", caret).finish(),
+            SlashAction::Transform(_) => f.write_str("Transform(..)"),
+            SlashAction::Run(_) => f.write_str("Run(..)"),
+        }
+    }
+}
+
+/// An entry of the slash command menu
+#[derive(Debug, Clone)]
+pub struct SlashCommand {
+    pub id: String,
+    pub label: String,
+    pub description: String,
+    pub icon: String,
+    /// Extra words matched by the filter
+    pub keywords: Vec<String>,
+    pub action: SlashAction,
+}
+
+impl SlashCommand {
+    pub fn new(id: &str, label: &str, description: &str, icon: &str, action: SlashAction) -> Self {
+        Self {
+            id: id.to_string(),
+            label: label.to_string(),
+            description: description.to_string(),
+            icon: icon.to_string(),
+            keywords: Vec::new(),
+            action,
+        }
+    }
+
+    pub fn with_keywords(mut self, keywords: &[&str]) -> Self {
+        self.keywords = keywords.iter().map(|k| k.to_string()).collect();
+        self
+    }
+}
+
+// Commands are identified by id; closures can't be compared
+impl PartialEq for SlashCommand {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+/// Commands computed from the current state when the menu opens
+type SlashProvider = Rc<dyn Fn(&AppState) -> Vec<SlashCommand>>;
+
+/// Commands offered by the `/` menu. Modules add fixed entries with
+/// `register` and state-dependent ones (one per template, say) with
+/// `register_provider`.
+#[derive(Clone)]
+pub struct SlashRegistry {
+    commands: Vec<SlashCommand>,
+    providers: Vec<(String, SlashProvider)>,
+}
+
+impl fmt::Debug for SlashRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlashRegistry")
+            .field("commands", &self.commands)
+            .field("providers", &self.providers.iter().map(|(id, _)| id).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Default for SlashRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for command in builtin_commands() {
+            registry.register(command);
+        }
+        crate::templates::register_slash_commands(&mut registry);
+        registry
+    }
+}
+
+impl SlashRegistry {
+    /// Registry without the built-in commands
+    pub fn empty() -> Self {
+        Self { commands: Vec::new(), providers: Vec::new() }
+    }
+
+    /// Add a command; a command with the same id is replaced
+    pub fn register(&mut self, command: SlashCommand) {
+        match self.commands.iter_mut().find(|c| c.id == command.id) {
+            Some(existing) => *existing = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    /// Add a source of commands that depend on the state; a provider with
+    /// the same id is replaced
+    pub fn register_provider(&mut self, id: &str, provider: impl Fn(&AppState) -> Vec<SlashCommand> + 'static) {
+        self.providers.retain(|(existing, _)| existing != id);
+        self.providers.push((id.to_string(), Rc::new(provider)));
+    }
+
+    /// Remove a command or a provider
+    pub fn unregister(&mut self, id: &str) {
+        self.commands.retain(|c| c.id != id);
+        self.providers.retain(|(existing, _)| existing != id);
+    }
+
+    /// Fixed commands followed by what the providers offer for `state`
+    pub fn commands(&self, state: &AppState) -> Vec<SlashCommand> {
+        let mut commands = self.commands.clone();
+        for (_, provider) in &self.providers {
+            commands.extend(provider(state));
+        }
+        commands
+    }
+
+    /// Commands matching `query`, best match first; all commands for an empty query
+    pub fn filter(&self, state: &AppState, query: &str) -> Vec<SlashCommand> {
+        let commands = self.commands(state);
+        if query.is_empty() {
+            return commands;
+        }
+
+        let mut scored: Vec<(i32, usize, SlashCommand)> = commands.into_iter()
+            .enumerate()
+            .filter_map(|(i, command)| {
+                let label = fuzzy_score(query, &command.label);
+                let keyword = command.keywords.iter().filter_map(|k| fuzzy_score(query, k)).max();
+                // Keyword hits rank below equally good label hits
+                let score = label.max(keyword.map(|k| k - 5))?;
+                Some((score, i, command))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored.into_iter().map(|(_, _, c)| c).collect()
+    }
+}
+
+/// The `/query` the caret is at the end of, with its byte range.
+/// The `/` must start the block or follow whitespace, so paths and URLs
+/// typed mid-sentence don't open the menu.
+pub fn find_slash_query(content: &str, caret: usize) -> Option<(String, Range<usize>)> {
+    let before = content.get(..caret.min(content.len()))?;
+    let re = Regex::new(r"(?:^|\s)(/([\w-]*))$").ok()?;
+    let cap = re.captures(before)?;
+    Some((cap.get(2)?.as_str().to_string(), cap.get(1)?.range()))
+}
+
+/// Apply a command in place of the `/query` at `range`.
+/// Returns the new content and the caret position, if the command sets one.
+/// `Run` commands only remove the query here; the caller runs them on the store.
+pub fn apply_slash_command(content: &str, range: Range<usize>, command: &SlashCommand) -> (String, Option<usize>) {
+    let before = &content[..range.start];
+    let after = &content[range.end..];
+
+    match &command.action {
+        SlashAction::Insert { text, caret } => {
+            let new_content = format!("{}{}{}", before, text, after);
+            let caret = Some(before.len() + caret.unwrap_or(text.len()));
+            (new_content, caret)
+        }
+        SlashAction::Transform(transform) => {
+            let stripped = format!("{}{}", before.trim_end(), after);
+            let new_content = transform(&stripped);
+            let caret = Some(new_content.len());
+            (new_content, caret)
+        }
+        SlashAction::Run(_) => {
+            let kept = before.trim_end();
+            (format!("{}{}", kept, after), Some(kept.len()))
+        }
+    }
+}
+
+/// Replace the heading prefix of the first line
+fn set_heading(content: &str, level: usize) -> String {
+    let (first, rest) = match content.split_once('\n') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (content, None),
+    };
+    let text = first.trim_start_matches('#').trim_start();
+    let heading = format!("{} {}", "#".repeat(level), text);
+    match rest {
+        Some(rest) => format!("{}\n{}", heading, rest),
+        None => heading,
+    }
+}
+
+fn heading_1(content: &str) -> String {
+    set_heading(content, 1)
+}
+
+fn heading_2(content: &str) -> String {
+    set_heading(content, 2)
+}
+
+fn heading_3(content: &str) -> String {
+    set_heading(content, 3)
+}
+
+fn todo(content: &str) -> String {
+    set_marker(content, TaskMarker::Todo)
+}
+
+fn builtin_commands() -> Vec<SlashCommand> {
+    let insert = |text: &str, caret: Option<usize>| SlashAction::Insert { text: text.to_string(), caret };
+    vec![
+        SlashCommand::new("heading-1", "Heading 1", "Large section heading", "H1", SlashAction::Transform(Rc::new(heading_1)))
+            .with_keywords(&["h1", "title"]),
+        SlashCommand::new("heading-2", "Heading 2", "Medium section heading", "H2", SlashAction::Transform(Rc::new(heading_2)))
+            .with_keywords(&["h2", "subtitle"]),
+        SlashCommand::new("heading-3", "Heading 3", "Small section heading", "H3", SlashAction::Transform(Rc::new(heading_3)))
+            .with_keywords(&["h3"]),
+        SlashCommand::new("todo", "TODO", "Turn the block into a task", "☐", SlashAction::Transform(Rc::new(todo)))
+            .with_keywords(&["task", "checkbox"]),
+        SlashCommand::new("date", "Date", "Link to a journal day, e.g. next friday", "📅", insert("/date ", None))
+            .with_keywords(&["today", "tomorrow", "journal"]),
+        SlashCommand::new("page-reference", "Page reference", "Link to a page", "[[", insert("[[]]", Some(2)))
+            .with_keywords(&["link", "wikilink"]),
+        SlashCommand::new("block-reference", "Block reference", "Reference another block", "((", insert("(())", Some(2)))
+            .with_keywords(&["ref"]),
+        SlashCommand::new("embed", "Embed", "Embed a page or block", "⧉", insert("{{embed [[]]}}", Some(10)))
+            .with_keywords(&["transclude"]),
+        SlashCommand::new("query", "Query", "Live query of blocks", "?", insert("{{query }}", Some(8)))
+            .with_keywords(&["search", "filter"]),
+        SlashCommand::new("code-block", "Code block", "Fenced code with highlighting", "</>", insert("```\n\n```", Some(4)))
+            .with_keywords(&["fence", "snippet", "pre"]),
+        SlashCommand::new("template", "Template", "Insert a template by name", "🧩", insert("/template ", None))
+            .with_keywords(&["snippet"]),
+        SlashCommand::new("table", "Table", "Two-column markdown table", "▦", insert("| Column | Column |\n| --- | --- |\n|  |  |", Some(2)))
+            .with_keywords(&["grid"]),
+        SlashCommand::new("horizontal-rule", "Horizontal rule", "Divider line", "―", insert("---", None))
+            .with_keywords(&["hr", "divider", "separator"]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_at_end_of_text() {
+        assert_eq!(find_slash_query("note /hea", 9), Some(("hea".to_string(), 5..9)));
+        assert_eq!(find_slash_query("/", 1), Some((String::new(), 0..1)));
+    }
+
+    #[test]
+    fn query_at_caret_mid_text() {
+        let text = "first /to second line";
+        assert_eq!(find_slash_query(text, 9), Some(("to".to_string(), 6..9)));
+        // Caret past the query: the text before it no longer ends in `/query`
+        assert_eq!(find_slash_query(text, text.len()), None);
+    }
+
+    #[test]
+    fn slash_inside_words_is_ignored() {
+        assert_eq!(find_slash_query("a/b", 3), None);
+        assert_eq!(find_slash_query("see https://x", 13), None);
+    }
+
+    #[test]
+    fn insert_at_caret_keeps_following_text() {
+        let registry = SlashRegistry::default();
+        let command = registry.commands(&AppState::default()).into_iter().find(|c| c.id == "page-reference").unwrap();
+        let (text, caret) = apply_slash_command("a /pa b", 2..5, &command);
+        assert_eq!(text, "a [[]] b");
+        assert_eq!(caret, Some(4));
+    }
+
+    #[test]
+    fn closures_can_capture_state() {
+        let signature = String::from("— Ada");
+        let mut registry = SlashRegistry::empty();
+        registry.register(SlashCommand::new(
+            "sign",
+            "Signature",
+            "Append a signature",
+            "✍",
+            SlashAction::Transform(Rc::new(move |text| format!("{} {}", text, signature))),
+        ));
+
+        let command = registry.filter(&AppState::default(), "sig").remove(0);
+        let (text, _) = apply_slash_command("Thanks /sig", 7..11, &command);
+        assert_eq!(text, "Thanks — Ada");
+    }
+
+    #[test]
+    fn providers_add_commands_from_state() {
+        let mut state = AppState::default();
+        state.slash_commands = SlashRegistry::empty();
+        state.slash_commands.register_provider("pages", |state| {
+            state.pages.values()
+                .map(|page| SlashCommand::new(&page.id, &page.title, "", "", SlashAction::Insert { text: page.title.clone(), caret: None }))
+                .collect()
+        });
+        state.create_page("Roadmap");
+
+        let labels: Vec<String> = state.slash_commands.filter(&state, "road").into_iter().map(|c| c.label).collect();
+        assert_eq!(labels, vec!["Roadmap".to_string()]);
+
+        state.slash_commands.unregister("pages");
+        assert!(state.slash_commands.commands(&state).is_empty());
+    }
+
+    #[test]
+    fn templates_register_run_commands() {
+        let mut state = AppState::default();
+        let templates = state.create_page("Templates");
+        state.insert_outline(&templates, "- template:: Meeting\n  - Agenda");
+        let target = state.create_page("Sync");
+        state.insert_outline(&target, "- /meet");
+        let block_id = state.pages[&target].blocks[0].clone();
+
+        let command = state.slash_commands.filter(&state, "meet").remove(0);
+        assert_eq!(command.label, "Template: Meeting");
+        let (text, caret) = apply_slash_command("/meet", 0..5, &command);
+        assert_eq!((text.as_str(), caret), ("", Some(0)));
+
+        let SlashAction::Run(run) = command.action else { panic!("expected a Run action") };
+        run(&mut state, &SlashTarget { page_id: target.clone(), block_id: block_id.clone() });
+        let blocks = &state.pages[&target].blocks;
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(state.blocks[&blocks[1]].content, "Agenda");
+        assert_eq!(state.current_block_id.as_ref(), Some(&blocks[1]));
+    }
+}
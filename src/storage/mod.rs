@@ -4,12 +4,26 @@ use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
 use crate::store::{Page, Block, Theme};
+use crate::journal::JournalConfig;
+use crate::reminders::{ReminderConfig, ReminderState};
+use crate::history::NavHistory;
+use crate::workspace::Workspace;
+use crate::right_sidebar::RightSidebar;
+use crate::graph::{GraphPreset, GraphSettings};
 
 /// Storage key prefixes
 const PREFIX_PAGES: &str = "dioxus_brain_pages_";
 const PREFIX_BLOCKS: &str = "dioxus_brain_blocks_";
 const PREFIX_STATE: &str = "dioxus_brain_state_";
 const PREFIX_FAVORITES: &str = "dioxus_brain_favorites_";
+const PREFIX_JOURNAL: &str = "dioxus_brain_journal_";
+const PREFIX_REMINDERS: &str = "dioxus_brain_reminders_";
+const PREFIX_HISTORY: &str = "dioxus_brain_history_";
+const PREFIX_WORKSPACE: &str = "dioxus_brain_workspace_";
+const PREFIX_RIGHT_SIDEBAR: &str = "dioxus_brain_right_sidebar_";
+const PREFIX_GRAPH_PINS: &str = "dioxus_brain_graph_pins_";
+const PREFIX_GRAPH_SETTINGS: &str = "dioxus_brain_graph_settings_";
+const PREFIX_GRAPH_PRESETS: &str = "dioxus_brain_graph_presets_";
 
 /// JSON-serializable page representation for storage
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +34,8 @@ struct StoredPage {
     blocks: Vec<String>,
     properties: HashMap<String, String>,
     tags: Vec<String>,
+    #[serde(default)]
+    journal_day: Option<String>,
     created_at: String,
     updated_at: String,
 }
@@ -33,6 +49,7 @@ impl From<Page> for StoredPage {
             blocks: page.blocks,
             properties: page.properties,
             tags: page.tags,
+            journal_day: page.journal_day.map(|d| d.to_string()),
             created_at: page.created_at.to_rfc3339(),
             updated_at: page.updated_at.to_rfc3339(),
         }
@@ -48,6 +65,7 @@ impl Into<Page> for StoredPage {
             blocks: self.blocks,
             properties: self.properties,
             tags: self.tags,
+            journal_day: self.journal_day.and_then(|d| d.parse().ok()),
             created_at: self.created_at.parse().unwrap_or_default(),
             updated_at: self.updated_at.parse().unwrap_or_default(),
         }
@@ -167,6 +185,20 @@ impl StorageManager {
         }).map(|sp| sp.into())
     }
 
+    /// Load a page and its blocks, following each block's children
+    pub fn load_page_tree(&self, page_id: &str) -> Option<(Page, Vec<Block>)> {
+        let page = self.load_page(page_id)?;
+        let mut blocks = Vec::new();
+        let mut pending = page.blocks.clone();
+        while let Some(id) = pending.pop() {
+            if let Some(block) = self.load_block(&id) {
+                pending.extend(block.children.iter().cloned());
+                blocks.push(block);
+            }
+        }
+        Some((page, blocks))
+    }
+
     /// Delete a page from storage
     pub fn delete_page(&self, page_id: &str) {
         self.remove_storage(&format!("{}{}", PREFIX_PAGES, page_id));
@@ -227,6 +259,118 @@ impl StorageManager {
         (favorites, theme)
     }
 
+    /// Save journal settings
+    pub fn save_journal_config(&self, config: &JournalConfig) {
+        if let Ok(json) = serde_json::to_string(config) {
+            self.set_storage(PREFIX_JOURNAL, &json);
+        }
+    }
+
+    /// Load journal settings
+    pub fn load_journal_config(&self) -> JournalConfig {
+        self.get_storage(PREFIX_JOURNAL)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save reminder settings and scheduler bookkeeping (fired/snoozed keys)
+    pub fn save_reminders(&self, config: &ReminderConfig, state: &ReminderState) {
+        if let Ok(json) = serde_json::to_string(&(config, state)) {
+            self.set_storage(PREFIX_REMINDERS, &json);
+        }
+    }
+
+    /// Load reminder settings and scheduler bookkeeping
+    pub fn load_reminders(&self) -> (ReminderConfig, ReminderState) {
+        self.get_storage(PREFIX_REMINDERS)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the page/block visit history
+    pub fn save_history(&self, history: &NavHistory) {
+        if let Ok(json) = serde_json::to_string(history) {
+            self.set_storage(PREFIX_HISTORY, &json);
+        }
+    }
+
+    /// Load the visit history
+    pub fn load_history(&self) -> NavHistory {
+        self.get_storage(PREFIX_HISTORY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save open tabs and split panes
+    pub fn save_workspace(&self, workspace: &Workspace) {
+        if let Ok(json) = serde_json::to_string(workspace) {
+            self.set_storage(PREFIX_WORKSPACE, &json);
+        }
+    }
+
+    /// Load the tab and pane layout
+    pub fn load_workspace(&self) -> Workspace {
+        self.get_storage(PREFIX_WORKSPACE)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the right sidebar stack and docked panels
+    pub fn save_right_sidebar(&self, sidebar: &RightSidebar) {
+        if let Ok(json) = serde_json::to_string(sidebar) {
+            self.set_storage(PREFIX_RIGHT_SIDEBAR, &json);
+        }
+    }
+
+    /// Load the right sidebar stack
+    pub fn load_right_sidebar(&self) -> RightSidebar {
+        self.get_storage(PREFIX_RIGHT_SIDEBAR)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the graph nodes pinned in place, by page id
+    pub fn save_graph_pins(&self, pins: &HashMap<String, (f64, f64)>) {
+        if let Ok(json) = serde_json::to_string(pins) {
+            self.set_storage(PREFIX_GRAPH_PINS, &json);
+        }
+    }
+
+    /// Load the pinned graph positions
+    pub fn load_graph_pins(&self) -> HashMap<String, (f64, f64)> {
+        self.get_storage(PREFIX_GRAPH_PINS)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the graph view's filters, groups and node sizing
+    pub fn save_graph_settings(&self, settings: &GraphSettings) {
+        if let Ok(json) = serde_json::to_string(settings) {
+            self.set_storage(PREFIX_GRAPH_SETTINGS, &json);
+        }
+    }
+
+    /// Load the graph view settings
+    pub fn load_graph_settings(&self) -> GraphSettings {
+        self.get_storage(PREFIX_GRAPH_SETTINGS)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the named graph presets
+    pub fn save_graph_presets(&self, presets: &[GraphPreset]) {
+        if let Ok(json) = serde_json::to_string(presets) {
+            self.set_storage(PREFIX_GRAPH_PRESETS, &json);
+        }
+    }
+
+    /// Load the named graph presets
+    pub fn load_graph_presets(&self) -> Vec<GraphPreset> {
+        self.get_storage(PREFIX_GRAPH_PRESETS)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
     /// Export all data as JSON
     pub fn export_all(&self) -> String {
         let pages: Vec<StoredPage> = self.pages.borrow()
@@ -4,6 +4,16 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::RwLock;
 use std::rc::Rc;
+use crate::journal::JournalConfig;
+use crate::reminders::{Reminder, ReminderConfig, ReminderState};
+use crate::templates::{TemplateInfo, TemplateInsert};
+use crate::slash::SlashRegistry;
+use crate::utils::slugify;
+use crate::history::{NavHistory, VisitTarget};
+use crate::workspace::Workspace;
+use crate::right_sidebar::{RightSidebar, SidebarTarget};
+use crate::graph::GraphLayout;
+use crate::utils::dates::DateLocale;
 
 /// Represents a block in the outliner structure
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -40,6 +50,9 @@ pub struct Page {
     pub blocks: Vec<String>,
     pub properties: HashMap<String, String>,
     pub tags: Vec<String>,
+    /// Set for journal pages to the day they cover
+    #[serde(default)]
+    pub journal_day: Option<chrono::NaiveDate>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -54,6 +67,7 @@ impl Default for Page {
             blocks: Vec::new(),
             properties: HashMap::new(),
             tags: Vec::new(),
+            journal_day: None,
             created_at: now,
             updated_at: now,
         }
@@ -70,10 +84,15 @@ impl Page {
             blocks: Vec::new(),
             properties: HashMap::new(),
             tags: Vec::new(),
+            journal_day: None,
             created_at: now,
             updated_at: now,
         }
     }
+
+    pub fn is_journal(&self) -> bool {
+        self.journal_day.is_some()
+    }
 }
 
 /// Theme preference
@@ -99,6 +118,12 @@ pub enum PageFilter {
     Tags(String),
 }
 
+impl Default for PageFilter {
+    fn default() -> Self {
+        PageFilter::All
+    }
+}
+
 /// The main application state (wrapped in Rc<RwLock> for shared mutability)
 #[derive(Debug, Clone, Default)]
 pub struct AppState {
@@ -106,12 +131,40 @@ pub struct AppState {
     pub blocks: HashMap<String, Block>,
     pub current_page_id: Option<String>,
     pub current_block_id: Option<String>,
+    /// Blocks selected in the outliner, in document order. `current_block_id`
+    /// is the end a Shift+Click or Shift+Arrow moves.
+    pub selected_blocks: Vec<String>,
+    /// Fixed end of the selection
+    pub selection_anchor: Option<String>,
     pub theme: Theme,
     pub left_sidebar_open: bool,
     pub right_sidebar_open: bool,
     pub favorites: Vec<String>,
     pub search_query: String,
     pub page_filter: PageFilter,
+    pub journal_config: JournalConfig,
+    /// Numeric date order for date links and phrases, detected at startup
+    pub date_locale: DateLocale,
+    pub reminder_config: ReminderConfig,
+    /// Scheduler bookkeeping: fired and snoozed reminders
+    pub reminders: ReminderState,
+    /// Fired reminders shown in the notification tray
+    pub notifications: Vec<Reminder>,
+    /// Block to open in edit mode when it next mounts
+    pub pending_edit: Option<String>,
+    /// Commands offered by the `/` menu in the block editor
+    pub slash_commands: SlashRegistry,
+    /// Page and block visits for back/forward and "recently visited"
+    pub history: NavHistory,
+    /// Set on the browser's popstate, so the next route change is recorded
+    /// as a back/forward step rather than a new visit
+    pub history_popped: bool,
+    /// Open tabs and split panes
+    pub workspace: Workspace,
+    /// Pages and blocks stacked in the right sidebar, and its docked panels
+    pub right_sidebar: RightSidebar,
+    /// Graph view layout, kept between visits so it resumes where it settled
+    pub graph_layout: GraphLayout,
 }
 
 impl AppState {
@@ -135,6 +188,23 @@ impl AppState {
         pages.into_iter().take(limit).collect()
     }
 
+    /// Pages by most recent visit, unlike `get_recent_pages` which is by edit
+    pub fn get_recently_visited(&self, limit: usize) -> Vec<&Page> {
+        self.history
+            .recent_pages(limit)
+            .into_iter()
+            .filter_map(|(id, _)| self.pages.get(&id))
+            .collect()
+    }
+
+    /// Whether a history entry's page or block still exists
+    pub fn visit_exists(&self, target: &VisitTarget) -> bool {
+        match target {
+            VisitTarget::Page(id) => self.pages.contains_key(id),
+            VisitTarget::Block(id) => self.blocks.contains_key(id),
+        }
+    }
+
     pub fn get_favorite_pages(&self) -> Vec<&Page> {
         self.favorites
             .iter()
@@ -143,10 +213,89 @@ impl AppState {
     }
 
     pub fn get_pages_by_tag(&self, tag: &str) -> Vec<&Page> {
-        self.pages
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        let mut pages: Vec<&Page> = self.pages
             .values()
-            .filter(|page| page.tags.contains(&tag.to_string()))
-            .collect()
+            .filter(|page| {
+                page.tags.iter().any(|t| t.to_lowercase() == tag) ||
+                page.blocks.iter().any(|block_id| self.block_has_tag(block_id, &tag))
+            })
+            .collect();
+        pages.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
+        pages
+    }
+
+    /// Check a block and its descendants for an inline `#tag`
+    fn block_has_tag(&self, block_id: &str, tag: &str) -> bool {
+        match self.blocks.get(block_id) {
+            Some(block) => {
+                crate::markdown::collect_tags(&crate::markdown::parse_inline(&block.content))
+                    .iter()
+                    .any(|t| t.to_lowercase() == tag) ||
+                block.children.iter().any(|child_id| self.block_has_tag(child_id, tag))
+            }
+            None => false,
+        }
+    }
+
+    /// Find a page by title (case-insensitive)
+    pub fn find_page_by_title(&self, title: &str) -> Option<&Page> {
+        let title = title.trim().to_lowercase();
+        self.pages.values().find(|p| p.title.to_lowercase() == title)
+    }
+
+    /// Find the page a `/page/:slug` URL points to: a page titled exactly
+    /// `slug`, then the page `page_slug` gives that slug
+    pub fn find_page_by_slug(&self, slug: &str) -> Option<&Page> {
+        if let Some(page) = self.pages.values().find(|p| p.title == slug) {
+            return Some(page);
+        }
+        if let Some(page) = self.pages.get(slug) {
+            return Some(page);
+        }
+        self.slug_owner(slug).or_else(|| {
+            // `<slug>-<id>` for pages whose titles share a slug
+            self.pages.values().find(|p| {
+                slug.strip_suffix(p.id.as_str())
+                    .and_then(|base| base.strip_suffix('-'))
+                    .is_some_and(|base| slugify(&p.title) == base)
+            })
+        })
+    }
+
+    /// URL slug of a page: `utils::slugify` of its title. When several titles
+    /// slugify to the same text, only the page titled exactly that (or else
+    /// the oldest) keeps it and the others get their id appended. Titles
+    /// without any letters or digits use the id.
+    pub fn page_slug(&self, page: &Page) -> String {
+        let slug = slugify(&page.title);
+        if slug.is_empty() {
+            return page.id.clone();
+        }
+        match self.slug_owner(&slug) {
+            Some(owner) if owner.id != page.id => format!("{}-{}", slug, page.id),
+            _ => slug,
+        }
+    }
+
+    /// The page that keeps `slug` unsuffixed
+    fn slug_owner(&self, slug: &str) -> Option<&Page> {
+        self.pages.values()
+            .filter(|p| slugify(&p.title) == slug)
+            .min_by(|a, b| {
+                (a.title != slug).cmp(&(b.title != slug))
+                    .then(a.created_at.cmp(&b.created_at))
+                    .then(a.id.cmp(&b.id))
+            })
+    }
+
+    /// Find the page that contains a block, following `parent_id` up to the root
+    pub fn find_page_for_block(&self, block_id: &str) -> Option<&Page> {
+        let mut root_id = block_id;
+        while let Some(parent_id) = self.blocks.get(root_id).and_then(|b| b.parent_id.as_deref()) {
+            root_id = parent_id;
+        }
+        self.pages.values().find(|p| p.blocks.iter().any(|id| id == root_id))
     }
 
     pub fn is_favorite(&self, page_id: &str) -> bool {
@@ -154,14 +303,108 @@ impl AppState {
     }
 
     pub fn create_page(&mut self, title: &str) -> String {
+        let id = self.add_page(title);
+        self.current_page_id = Some(id.clone());
+        self.current_block_id = None;
+        id
+    }
+
+    /// Create a page without opening it, e.g. for a link typed into a block
+    pub fn add_page(&mut self, title: &str) -> String {
         let page = Page::new(title);
         let id = page.id.clone();
         self.pages.insert(id.clone(), page);
-        self.current_page_id = Some(id.clone());
-        self.current_block_id = None;
         id
     }
 
+    /// Journal pages, newest first
+    pub fn get_journal_pages(&self) -> Vec<&Page> {
+        let mut pages: Vec<&Page> = self.pages.values().filter(|p| p.is_journal()).collect();
+        pages.sort_by(|a, b| b.journal_day.cmp(&a.journal_day));
+        pages
+    }
+
+    /// Find the journal page for a date.
+    /// Pages created before journals were tracked are matched by title.
+    pub fn find_journal(&self, date: &chrono::NaiveDate) -> Option<&Page> {
+        self.pages.values()
+            .find(|p| p.journal_day.as_ref() == Some(date))
+            .or_else(|| {
+                let title = self.journal_config.title_for(date);
+                self.pages.values().find(|p| p.title == title)
+            })
+    }
+
+    /// Resolve a link target to a journal date, using the journal title
+    /// format first and natural date phrases second
+    pub fn resolve_journal_link(&self, title: &str, locale: &DateLocale) -> Option<chrono::NaiveDate> {
+        self.journal_config.parse_title(title)
+            .or_else(|| crate::utils::dates::resolve_date_link(title, crate::journal::today(), locale))
+    }
+
+    /// Put a page loaded from storage, and its blocks, back in memory.
+    /// Pages already in memory are kept as they are.
+    pub fn restore_page(&mut self, page: Page, blocks: Vec<Block>) {
+        if self.pages.contains_key(&page.id) {
+            return;
+        }
+        for block in blocks {
+            self.blocks.entry(block.id.clone()).or_insert(block);
+        }
+        self.pages.insert(page.id.clone(), page);
+    }
+
+    /// Create the journal page for a date without navigating to it.
+    /// Returns the existing page id if there already is one.
+    pub fn create_journal(&mut self, date: chrono::NaiveDate) -> String {
+        if let Some(id) = self.find_journal(&date).map(|p| p.id.clone()) {
+            if let Some(page) = self.pages.get_mut(&id) {
+                page.journal_day = Some(date);
+            }
+            return id;
+        }
+
+        let mut page = Page::new(&self.journal_config.title_for(&date));
+        page.icon = Some("📅".to_string());
+        page.journal_day = Some(date);
+        let id = page.id.clone();
+        self.pages.insert(id.clone(), page);
+
+        let template = self.journal_config.template.clone();
+        if !template.trim().is_empty() {
+            self.insert_outline(&id, &template);
+        }
+
+        id
+    }
+
+    /// Append a `- item` outline to a page as nested blocks
+    pub fn insert_outline(&mut self, page_id: &str, text: &str) -> Vec<String> {
+        let mut created = Vec::new();
+        // Stack of (depth, block id) for the current ancestor chain
+        let mut stack: Vec<(usize, String)> = Vec::new();
+
+        for (depth, content) in crate::journal::parse_outline(text) {
+            while stack.last().map_or(false, |(d, _)| *d >= depth) {
+                stack.pop();
+            }
+            let parent_id = stack.last().map(|(_, id)| id.clone());
+            let block_id = self.create_block(parent_id.clone());
+            if let Some(block) = self.blocks.get_mut(&block_id) {
+                block.content = content;
+            }
+            if parent_id.is_none() {
+                if let Some(page) = self.pages.get_mut(page_id) {
+                    page.blocks.push(block_id.clone());
+                }
+            }
+            stack.push((depth, block_id.clone()));
+            created.push(block_id);
+        }
+
+        created
+    }
+
     pub fn create_block(&mut self, parent_id: Option<String>) -> String {
         let block = Block {
             parent_id: parent_id.clone(),
@@ -266,8 +509,24 @@ pub fn use_store() -> GlobalStore {
 
 /// Extension trait for convenient state mutations
 pub trait AppStateExt {
-    fn set_current_page(&mut self, id: Option<String>);
+    fn set_current_page(&mut self, id: Option<String>) -> Option<VisitTarget>;
     fn set_current_block(&mut self, id: Option<String>);
+    fn select_block(&mut self, block_id: &str, extend: bool);
+    fn extend_selection(&mut self, forward: bool);
+    fn clear_selection(&mut self);
+    fn delete_selected(&mut self);
+    fn indent_selected(&mut self);
+    fn outdent_selected(&mut self);
+    fn move_selected(&mut self, page_id: &str) -> bool;
+    fn selected_markdown(&self) -> String;
+    fn todo_selected(&mut self);
+    fn set_property_selected(&mut self, key: &str, value: &str);
+    fn collapse_selected(&mut self);
+    fn toggle_collapsed(&mut self, block_id: &str);
+    fn record_visit(&mut self, target: VisitTarget, traversed: bool);
+    fn go_back(&mut self) -> Option<VisitTarget>;
+    fn go_forward(&mut self) -> Option<VisitTarget>;
+    fn expand_current_page(&mut self, level: Option<usize>);
     fn set_theme(&mut self, theme: Theme);
     fn toggle_theme(&mut self);
     fn set_left_sidebar_open(&mut self, open: bool);
@@ -276,6 +535,15 @@ pub trait AppStateExt {
     fn remove_favorite(&mut self, page_id: &str);
     fn toggle_favorite(&mut self, page_id: &str);
     fn set_search_query(&mut self, query: &str);
+    fn set_page_filter(&mut self, filter: PageFilter);
+    fn open_page_by_title(&mut self, title: &str) -> String;
+    fn open_page_in_split(&mut self, title: &str) -> String;
+    fn open_in_sidebar(&mut self, target: SidebarTarget);
+    fn open_page_in_sidebar(&mut self, title: &str) -> String;
+    fn open_block(&mut self, block_id: &str) -> Option<VisitTarget>;
+    fn open_journal(&mut self, date: chrono::NaiveDate) -> String;
+    fn ensure_today_journal(&mut self);
+    fn insert_template(&mut self, template: &TemplateInfo, page_id: &str, after: Option<&str>) -> Result<TemplateInsert, String>;
     fn update_page_title(&mut self, page_id: &str, title: &str);
     fn update_block_content(&mut self, block_id: &str, content: &str);
     fn add_tag(&mut self, page_id: &str, tag: &str);
@@ -283,15 +551,167 @@ pub trait AppStateExt {
 }
 
 impl AppStateExt for GlobalStore {
-    fn set_current_page(&mut self, id: Option<String>) {
+    /// Make a page current. Returns the page to show, if it exists; the
+    /// caller navigates to it.
+    fn set_current_page(&mut self, id: Option<String>) -> Option<VisitTarget> {
+        let target = id.as_ref().filter(|id| self.read().pages.contains_key(*id)).map(|id| VisitTarget::Page(id.clone()));
         self.write().current_page_id = id;
         self.write().current_block_id = None;
+        self.clear_selection();
+        target
     }
 
     fn set_current_block(&mut self, id: Option<String>) {
         self.write().current_block_id = id;
     }
 
+    /// Select a block; with `extend`, select everything from the anchor to it
+    fn select_block(&mut self, block_id: &str, extend: bool) {
+        let mut state = self.write();
+        let page_id = match state.find_page_for_block(block_id) {
+            Some(page) => page.id.clone(),
+            None => return,
+        };
+        // Shift+Click from a focused block selects from that block
+        let anchor = state.selection_anchor.clone()
+            .or_else(|| state.current_block_id.clone())
+            .filter(|a| extend && state.find_page_for_block(a).map(|p| p.id == page_id).unwrap_or(false));
+
+        match anchor {
+            Some(anchor) => {
+                let order = crate::outline::visible_order(&state, &page_id);
+                state.selected_blocks = crate::outline::selection_range(&order, &anchor, block_id);
+                // Keep a fallback anchor fixed, since the focus moves below
+                state.selection_anchor = Some(anchor);
+            }
+            None => {
+                state.selection_anchor = Some(block_id.to_string());
+                state.selected_blocks = vec![block_id.to_string()];
+            }
+        }
+        state.current_block_id = Some(block_id.to_string());
+    }
+
+    /// Move the selection end to the previous or next block
+    fn extend_selection(&mut self, forward: bool) {
+        let next = {
+            let state = self.read();
+            let focus = match state.current_block_id.clone() {
+                Some(focus) => focus,
+                None => return,
+            };
+            let order = match state.find_page_for_block(&focus) {
+                Some(page) => crate::outline::visible_order(&state, &page.id),
+                None => return,
+            };
+            crate::outline::adjacent_block(&order, &focus, forward)
+        };
+        if let Some(next) = next {
+            self.select_block(&next, true);
+        }
+    }
+
+    fn clear_selection(&mut self) {
+        let mut state = self.write();
+        state.selected_blocks.clear();
+        state.selection_anchor = None;
+    }
+
+    fn delete_selected(&mut self) {
+        if let Some((page_id, roots)) = selection_targets(self) {
+            crate::outline::remove_blocks(&mut self.write(), &page_id, &roots);
+            self.write().current_block_id = None;
+            self.clear_selection();
+        }
+    }
+
+    fn indent_selected(&mut self) {
+        if let Some((page_id, roots)) = selection_targets(self) {
+            crate::outline::indent_blocks(&mut self.write(), &page_id, &roots);
+        }
+    }
+
+    fn outdent_selected(&mut self) {
+        if let Some((page_id, roots)) = selection_targets(self) {
+            crate::outline::outdent_blocks(&mut self.write(), &page_id, &roots);
+        }
+    }
+
+    /// Move the selected blocks to the end of another page
+    fn move_selected(&mut self, page_id: &str) -> bool {
+        let moved = match selection_targets(self) {
+            Some((from, roots)) => crate::outline::move_blocks(&mut self.write(), &from, page_id, &roots),
+            None => false,
+        };
+        if moved {
+            self.clear_selection();
+        }
+        moved
+    }
+
+    /// The selected blocks as a markdown outline, for copying
+    fn selected_markdown(&self) -> String {
+        match selection_targets(self) {
+            Some((_, roots)) => crate::outline::blocks_to_markdown(&self.read(), &roots),
+            None => String::new(),
+        }
+    }
+
+    fn todo_selected(&mut self) {
+        let selected = self.read().selected_blocks.clone();
+        crate::outline::mark_todo(&mut self.write(), &selected);
+    }
+
+    fn set_property_selected(&mut self, key: &str, value: &str) {
+        let key = key.trim();
+        if key.is_empty() {
+            return;
+        }
+        let selected = self.read().selected_blocks.clone();
+        crate::outline::set_blocks_property(&mut self.write(), &selected, key, value.trim());
+    }
+
+    /// Collapse selected blocks that have children
+    fn collapse_selected(&mut self) {
+        let selected = self.read().selected_blocks.clone();
+        let mut state = self.write();
+        for id in &selected {
+            crate::outline::set_collapsed(&mut state, id, true);
+        }
+    }
+
+    /// Record a route change; `traversed` when the browser's back/forward caused it
+    fn record_visit(&mut self, target: VisitTarget, traversed: bool) {
+        self.write().history.visit(target, chrono::Utc::now(), traversed);
+    }
+
+    /// Step back to the previous visit that still exists and return it for
+    /// the caller to navigate to
+    fn go_back(&mut self) -> Option<VisitTarget> {
+        step_history(self, false)
+    }
+
+    /// Step forward to the next visit that still exists and return it
+    fn go_forward(&mut self) -> Option<VisitTarget> {
+        step_history(self, true)
+    }
+
+    fn toggle_collapsed(&mut self, block_id: &str) {
+        let collapsed = match self.read().blocks.get(block_id) {
+            Some(block) => crate::outline::is_collapsed(block),
+            None => return,
+        };
+        crate::outline::set_collapsed(&mut self.write(), block_id, !collapsed);
+    }
+
+    /// Expand the current page's outline to `level` levels, or fully with `None`
+    fn expand_current_page(&mut self, level: Option<usize>) {
+        let page_id = self.read().current_page_id.clone();
+        if let Some(page_id) = page_id {
+            crate::outline::expand_to_level(&mut self.write(), &page_id, level);
+        }
+    }
+
     fn set_theme(&mut self, theme: Theme) {
         self.write().theme = theme;
         #[cfg(feature = "web")]
@@ -345,6 +765,84 @@ impl AppStateExt for GlobalStore {
         self.write().search_query = query.to_string();
     }
 
+    fn set_page_filter(&mut self, filter: PageFilter) {
+        self.write().page_filter = filter;
+    }
+
+    /// Make a page current by title, creating it if it does not exist yet.
+    /// Date links like `[[2026-10-16]]` or `[[next friday]]` open journal pages.
+    fn open_page_by_title(&mut self, title: &str) -> String {
+        let existing = self.read().find_page_by_title(title).map(|p| p.id.clone());
+        if let Some(id) = existing {
+            self.set_current_page(Some(id.clone()));
+            return id;
+        }
+
+        let journal_date = {
+            let state = self.read();
+            state.resolve_journal_link(title, &state.date_locale)
+        };
+        match journal_date {
+            Some(date) => self.open_journal(date),
+            None => {
+                let id = self.write().add_page(title.trim());
+                self.set_current_page(Some(id.clone()));
+                id
+            }
+        }
+    }
+
+    /// Open a page by title in the pane next to the focused one, creating
+    /// the page if needed. The focused pane and the URL stay as they are.
+    fn open_page_in_split(&mut self, title: &str) -> String {
+        let id = resolve_page_title(self, title);
+        self.write().workspace.open_in_split(&id);
+        id
+    }
+
+    /// Put a page or block on top of the right sidebar stack and show the sidebar
+    fn open_in_sidebar(&mut self, target: SidebarTarget) {
+        let mut state = self.write();
+        state.right_sidebar.open(target);
+        state.right_sidebar_open = true;
+    }
+
+    /// Open a page by title in the right sidebar, creating the page if needed
+    fn open_page_in_sidebar(&mut self, title: &str) -> String {
+        let id = resolve_page_title(self, title);
+        self.open_in_sidebar(SidebarTarget::Page(id.clone()));
+        id
+    }
+
+    /// Make the page containing a block current and focus that block.
+    /// Returns the page to show.
+    fn open_block(&mut self, block_id: &str) -> Option<VisitTarget> {
+        let page_id = self.read().find_page_for_block(block_id).map(|p| p.id.clone())?;
+        let target = self.set_current_page(Some(page_id));
+        self.set_current_block(Some(block_id.to_string()));
+        target
+    }
+
+    /// Make the journal page for a date current, creating it from the template if needed
+    fn open_journal(&mut self, date: chrono::NaiveDate) -> String {
+        let id = self.write().create_journal(date);
+        self.set_current_page(Some(id.clone()));
+        id
+    }
+
+    /// Insert a template after `after` (or at the end of the page) and open
+    /// its `{{cursor}}` block, or its first block, for editing
+    fn insert_template(&mut self, template: &TemplateInfo, page_id: &str, after: Option<&str>) -> Result<TemplateInsert, String> {
+        crate::templates::insert(&mut self.write(), template, page_id, after)
+    }
+
+    /// Create today's journal page on launch when enabled
+    fn ensure_today_journal(&mut self) {
+        if self.read().journal_config.auto_create {
+            self.write().create_journal(crate::journal::today());
+        }
+    }
+
     fn update_page_title(&mut self, page_id: &str, title: &str) {
         if let Some(page) = self.write().pages.get_mut(page_id) {
             page.title = title.to_string();
@@ -377,3 +875,158 @@ impl AppStateExt for GlobalStore {
         }
     }
 }
+
+/// Page of the current selection and its top-most selected blocks
+fn selection_targets(store: &GlobalStore) -> Option<(String, Vec<String>)> {
+    let state = store.read();
+    let first = state.selected_blocks.first()?;
+    let page_id = state.find_page_for_block(first)?.id.clone();
+    let roots = crate::outline::selection_roots(&state, &state.selected_blocks);
+    Some((page_id, roots))
+}
+
+/// Move through the history, skipping deleted pages and blocks, and show the visit
+fn step_history(store: &mut GlobalStore, forward: bool) -> Option<VisitTarget> {
+    let mut state = store.write();
+    let mut history = std::mem::take(&mut state.history);
+    let target = history.step(forward, |target| state.visit_exists(target)).map(|v| v.target.clone());
+    state.history = history;
+    target
+}
+
+/// Id of the page a link title points to, creating the page (or journal page)
+/// without navigating to it
+fn resolve_page_title(store: &mut GlobalStore, title: &str) -> String {
+    let existing = store.read().find_page_by_title(title).map(|p| p.id.clone());
+    if let Some(id) = existing {
+        return id;
+    }
+    let journal_date = {
+        let state = store.read();
+        state.resolve_journal_link(title, &state.date_locale)
+    };
+    match journal_date {
+        Some(date) => store.write().create_journal(date),
+        None => store.write().add_page(title.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_page_keeps_the_current_page() {
+        let mut state = AppState::default();
+        let open = state.create_page("Open");
+        state.current_block_id = Some("editing".to_string());
+
+        let added = state.add_page("Linked");
+        assert!(state.pages.contains_key(&added));
+        assert_eq!(state.current_page_id, Some(open));
+        assert_eq!(state.current_block_id.as_deref(), Some("editing"));
+    }
+
+    #[test]
+    fn slug_lookup_prefers_exact_titles() {
+        let mut state = AppState::default();
+        let spaced = state.add_page("My Page");
+        let dashed = state.add_page("my-page");
+
+        assert_eq!(state.find_page_by_slug("my-page").map(|p| p.id.clone()), Some(dashed.clone()));
+        assert_eq!(state.page_slug(&state.pages[&dashed]), "my-page");
+        assert_eq!(state.page_slug(&state.pages[&spaced]), format!("my-page-{}", spaced));
+    }
+
+    #[test]
+    fn colliding_slugs_stay_routable() {
+        let mut state = AppState::default();
+        let pages: Vec<String> = ["Rust!", "Rust?", "RUST"].iter().map(|t| state.add_page(t)).collect();
+
+        let slugs: Vec<String> = pages.iter().map(|id| state.page_slug(&state.pages[id])).collect();
+        for (id, slug) in pages.iter().zip(&slugs) {
+            assert_eq!(state.find_page_by_slug(slug).map(|p| &p.id), Some(id), "slug {}", slug);
+        }
+        assert_eq!(slugs.iter().filter(|s| s.as_str() == "rust").count(), 1);
+    }
+
+    #[test]
+    fn titles_without_slug_characters_use_the_id() {
+        let mut state = AppState::default();
+        let id = state.add_page("???");
+        let slug = state.page_slug(&state.pages[&id]);
+        assert_eq!(slug, id);
+        assert_eq!(state.find_page_by_slug(&slug).map(|p| &p.id), Some(&id));
+    }
+
+    #[test]
+    fn shift_click_keeps_the_focused_block_as_anchor() {
+        let mut store = GlobalStore::new();
+        let ids = {
+            let mut state = store.write();
+            let page = state.create_page("Outline");
+            let ids = state.insert_outline(&page, "- a\n- b\n- c\n- d");
+            state.current_block_id = Some(ids[1].clone());
+            ids
+        };
+
+        store.select_block(&ids[2], true);
+        assert_eq!(store.read().selected_blocks, &ids[1..3]);
+        // The focus moved to the clicked block; the anchor stays put
+        store.extend_selection(true);
+        assert_eq!(store.read().selected_blocks, &ids[1..]);
+        store.extend_selection(false);
+        store.extend_selection(false);
+        assert_eq!(store.read().selected_blocks, &ids[1..2]);
+    }
+
+    #[test]
+    fn page_changes_return_what_to_show() {
+        let mut store = GlobalStore::new();
+        let (page, block) = {
+            let mut state = store.write();
+            let page = state.add_page("Target");
+            let block = state.insert_outline(&page, "- a")[0].clone();
+            (page, block)
+        };
+
+        assert_eq!(store.set_current_page(Some(page.clone())), Some(VisitTarget::Page(page.clone())));
+        assert_eq!(store.set_current_page(Some("missing".to_string())), None);
+        assert_eq!(store.read().current_page_id.as_deref(), Some("missing"));
+        assert_eq!(store.set_current_page(None), None);
+
+        assert_eq!(store.open_block(&block), Some(VisitTarget::Page(page.clone())));
+        assert_eq!(store.read().current_block_id.as_deref(), Some(block.as_str()));
+        assert_eq!(store.open_block("missing"), None);
+    }
+
+    #[test]
+    fn history_steps_return_the_visit_to_show() {
+        let mut store = GlobalStore::new();
+        let pages: Vec<String> = ["A", "B", "C"].iter().map(|t| store.write().add_page(t)).collect();
+        for id in &pages {
+            store.record_visit(VisitTarget::Page(id.clone()), false);
+        }
+        store.write().pages.remove(&pages[1]);
+
+        assert_eq!(store.go_back(), Some(VisitTarget::Page(pages[0].clone())));
+        assert_eq!(store.go_back(), None);
+        assert_eq!(store.go_forward(), Some(VisitTarget::Page(pages[2].clone())));
+        assert_eq!(store.go_forward(), None);
+    }
+
+    #[test]
+    fn restored_pages_do_not_replace_loaded_ones() {
+        let mut state = AppState::default();
+        let mut saved = Page::new("Saved");
+        let block = Block { content: "kept".to_string(), ..Default::default() };
+        saved.blocks.push(block.id.clone());
+        state.restore_page(saved.clone(), vec![block.clone()]);
+        assert_eq!(state.blocks[&block.id].content, "kept");
+
+        let mut stale = saved.clone();
+        stale.title = "Stale".to_string();
+        state.restore_page(stale, Vec::new());
+        assert_eq!(state.pages[&saved.id].title, "Saved");
+    }
+}
@@ -0,0 +1,505 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use crate::store::Block;
+
+/// Task state keyword at the start of a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskMarker {
+    Todo,
+    Doing,
+    Now,
+    Later,
+    Waiting,
+    Done,
+    Canceled,
+}
+
+impl TaskMarker {
+    pub fn parse(word: &str) -> Option<Self> {
+        match word {
+            "TODO" => Some(TaskMarker::Todo),
+            "DOING" => Some(TaskMarker::Doing),
+            "NOW" => Some(TaskMarker::Now),
+            "LATER" => Some(TaskMarker::Later),
+            "WAITING" => Some(TaskMarker::Waiting),
+            "DONE" => Some(TaskMarker::Done),
+            "CANCELED" | "CANCELLED" => Some(TaskMarker::Canceled),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskMarker::Todo => "TODO",
+            TaskMarker::Doing => "DOING",
+            TaskMarker::Now => "NOW",
+            TaskMarker::Later => "LATER",
+            TaskMarker::Waiting => "WAITING",
+            TaskMarker::Done => "DONE",
+            TaskMarker::Canceled => "CANCELED",
+        }
+    }
+
+    /// Done and canceled tasks no longer need attention
+    pub fn is_closed(&self) -> bool {
+        matches!(self, TaskMarker::Done | TaskMarker::Canceled)
+    }
+}
+
+/// Task marker of block content, if it starts with one
+pub fn task_marker(content: &str) -> Option<TaskMarker> {
+    content.split_whitespace().next().and_then(TaskMarker::parse)
+}
+
+/// Split block content into its task marker and the remaining text
+pub fn strip_marker(content: &str) -> (Option<TaskMarker>, &str) {
+    let trimmed = content.trim_start();
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    match TaskMarker::parse(&trimmed[..end]) {
+        Some(marker) => (Some(marker), trimmed[end..].trim_start()),
+        None => (None, content),
+    }
+}
+
+/// First line of block content without its marker or planning lines
+pub fn task_title(content: &str) -> String {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with("SCHEDULED:") && !l.starts_with("DEADLINE:"))
+        .unwrap_or_default();
+
+    match line.split_once(char::is_whitespace) {
+        Some((first, rest)) if TaskMarker::parse(first).is_some() => rest.trim().to_string(),
+        _ if TaskMarker::parse(line).is_some() => String::new(),
+        _ => line.to_string(),
+    }
+}
+
+/// An org-style timestamp such as `<2026-10-20 Mon 14:00 .+1w>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timestamp {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+    /// Raw repeater cookie (`+1w`, `++2d`, `.+1m`), if any
+    pub repeater: Option<String>,
+}
+
+impl Timestamp {
+    pub fn new(date: NaiveDate) -> Self {
+        Self { date, time: None, repeater: None }
+    }
+
+    /// Parsed repeater cookie, if the timestamp has a valid one
+    pub fn repeat(&self) -> Option<Repeater> {
+        self.repeater.as_deref().and_then(Repeater::parse)
+    }
+
+    /// The occurrence after this one when the task is completed on `today`
+    pub fn next_occurrence(&self, today: NaiveDate) -> Option<Timestamp> {
+        let repeat = self.repeat()?;
+        Some(Timestamp { date: repeat.next_date(self.date, today)?, ..self.clone() })
+    }
+
+    /// Format back into `<YYYY-MM-DD Day [HH:MM] [repeater]>`
+    pub fn to_org(&self) -> String {
+        let mut out = format!("<{}", self.date.format("%Y-%m-%d %a"));
+        if let Some(time) = self.time {
+            out.push_str(&format!(" {}", time.format("%H:%M")));
+        }
+        if let Some(repeater) = &self.repeater {
+            out.push(' ');
+            out.push_str(repeater);
+        }
+        out.push('>');
+        out
+    }
+}
+
+/// How a repeater moves the date when the task is completed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatKind {
+    /// `+1w`: shift once from the current date, even if still in the past
+    Cumulate,
+    /// `++1w`: shift until the date is in the future, keeping the weekday
+    CatchUp,
+    /// `.+1w`: shift from the completion date
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatUnit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// Org-style repeat rule such as `.+1w`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repeater {
+    pub kind: RepeatKind,
+    pub amount: u32,
+    pub unit: RepeatUnit,
+}
+
+impl Repeater {
+    pub fn parse(cookie: &str) -> Option<Self> {
+        let (kind, rest) = if let Some(rest) = cookie.strip_prefix(".+") {
+            (RepeatKind::Restart, rest)
+        } else if let Some(rest) = cookie.strip_prefix("++") {
+            (RepeatKind::CatchUp, rest)
+        } else {
+            (RepeatKind::Cumulate, cookie.strip_prefix('+')?)
+        };
+
+        let unit = match rest.chars().last()? {
+            'd' => RepeatUnit::Day,
+            'w' => RepeatUnit::Week,
+            'm' => RepeatUnit::Month,
+            'y' => RepeatUnit::Year,
+            _ => return None,
+        };
+        let amount: u32 = rest[..rest.len() - 1].parse().ok().filter(|n| *n > 0)?;
+
+        Some(Self { kind, amount, unit })
+    }
+
+    /// Human-readable rule, e.g. `every 2 weeks`
+    pub fn describe(&self) -> String {
+        let unit = match self.unit {
+            RepeatUnit::Day => "day",
+            RepeatUnit::Week => "week",
+            RepeatUnit::Month => "month",
+            RepeatUnit::Year => "year",
+        };
+        let every = if self.amount == 1 {
+            format!("every {}", unit)
+        } else {
+            format!("every {} {}s", self.amount, unit)
+        };
+        match self.kind {
+            RepeatKind::Restart => format!("{} after completion", every),
+            _ => every,
+        }
+    }
+
+    /// One repeat after `date`; `None` past the supported date range, since
+    /// the amount comes from user text
+    fn step(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let amount = self.amount as i64;
+        match self.unit {
+            RepeatUnit::Day => crate::utils::dates::add_days(date, amount),
+            RepeatUnit::Week => crate::utils::dates::add_days(date, amount.checked_mul(7)?),
+            RepeatUnit::Month => crate::utils::dates::add_months(date, amount),
+            RepeatUnit::Year => crate::utils::dates::add_months(date, amount.checked_mul(12)?),
+        }
+    }
+
+    /// Next date for a task dated `date` that is completed on `today`
+    pub fn next_date(&self, date: NaiveDate, today: NaiveDate) -> Option<NaiveDate> {
+        match self.kind {
+            RepeatKind::Cumulate => self.step(date),
+            RepeatKind::CatchUp => {
+                let mut next = self.step(date)?;
+                while next <= today {
+                    next = self.step(next)?;
+                }
+                Some(next)
+            }
+            RepeatKind::Restart => self.step(today),
+        }
+    }
+}
+
+/// Property that records completion times of recurring tasks
+pub const COMPLETED_PROPERTY: &str = "completed";
+
+/// Replace the task marker of block content, adding one if missing
+pub fn set_marker(content: &str, marker: TaskMarker) -> String {
+    let trimmed = content.trim_start();
+    match trimmed.split_once(char::is_whitespace) {
+        Some((first, rest)) if TaskMarker::parse(first).is_some() => format!("{} {}", marker.as_str(), rest),
+        _ if TaskMarker::parse(trimmed).is_some() => marker.as_str().to_string(),
+        _ => format!("{} {}", marker.as_str(), content),
+    }
+}
+
+/// Mark a task done. Tasks with a repeater stay open: their planning dates
+/// advance to the next occurrence and the completion time is appended to
+/// the `completed::` property.
+pub fn complete_task(content: &str, now: NaiveDateTime) -> String {
+    let planning = parse_planning(content);
+    let repeats = [&planning.scheduled, &planning.deadline]
+        .iter()
+        .any(|ts| ts.as_ref().and_then(|ts| ts.repeat()).is_some());
+    if !repeats {
+        return set_marker(content, TaskMarker::Done);
+    }
+
+    let today = now.date();
+    let advanced = match Regex::new(r"(SCHEDULED|DEADLINE):(\s*)<([^>]+)>") {
+        Ok(re) => re
+            .replace_all(content, |cap: &regex::Captures| {
+                let next = parse_timestamp(&cap[3]).and_then(|ts| ts.next_occurrence(today));
+                match next {
+                    Some(next) => format!("{}:{}{}", &cap[1], &cap[2], next.to_org()),
+                    None => cap[0].to_string(),
+                }
+            })
+            .into_owned(),
+        Err(_) => content.to_string(),
+    };
+
+    append_completion(&advanced, now)
+}
+
+/// Reopen a closed task, or complete an open one
+pub fn toggle_task(content: &str, now: NaiveDateTime) -> String {
+    match task_marker(content) {
+        Some(marker) if marker.is_closed() => set_marker(content, TaskMarker::Todo),
+        _ => complete_task(content, now),
+    }
+}
+
+/// Append a completion time to the `completed::` property line
+fn append_completion(content: &str, now: NaiveDateTime) -> String {
+    let stamp = now.format("%Y-%m-%d %H:%M").to_string();
+    let prefix = format!("{}::", COMPLETED_PROPERTY);
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    match lines.iter_mut().find(|l| l.trim_start().starts_with(&prefix)) {
+        Some(line) => {
+            let existing = line.trim_end().to_string();
+            *line = if existing.trim_end_matches(&prefix).trim().is_empty() {
+                format!("{} {}", existing, stamp)
+            } else {
+                format!("{}, {}", existing, stamp)
+            };
+        }
+        None => lines.push(format!("{} {}", prefix, stamp)),
+    }
+
+    lines.join("\n")
+}
+
+/// Scheduled and deadline dates of a block
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Planning {
+    pub scheduled: Option<Timestamp>,
+    pub deadline: Option<Timestamp>,
+}
+
+impl Planning {
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_none() && self.deadline.is_none()
+    }
+}
+
+/// `SCHEDULED: <...>` or `DEADLINE: <...>`; compiled once since calendars
+/// and the reminder scheduler run it over every block
+static PLANNING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(SCHEDULED|DEADLINE):\s*<([^>]+)>").unwrap());
+
+/// Parse `SCHEDULED: <...>` and `DEADLINE: <...>` lines from block content
+pub fn parse_planning(content: &str) -> Planning {
+    let mut planning = Planning::default();
+
+    for cap in PLANNING_RE.captures_iter(content) {
+        let timestamp = match cap.get(2).and_then(|m| parse_timestamp(m.as_str())) {
+            Some(ts) => ts,
+            None => continue,
+        };
+        match cap.get(1).map(|m| m.as_str()) {
+            Some("SCHEDULED") => planning.scheduled = Some(timestamp),
+            Some("DEADLINE") => planning.deadline = Some(timestamp),
+            _ => {}
+        }
+    }
+
+    planning
+}
+
+/// Parse the inside of an org timestamp: `2026-10-20 Mon 14:00 .+1w`
+pub fn parse_timestamp(text: &str) -> Option<Timestamp> {
+    let mut parts = text.split_whitespace();
+    let date = NaiveDate::parse_from_str(parts.next()?, "%Y-%m-%d").ok()?;
+    let mut timestamp = Timestamp::new(date);
+
+    for part in parts {
+        if let Ok(time) = NaiveTime::parse_from_str(part, "%H:%M") {
+            timestamp.time = Some(time);
+        } else if part.starts_with('+') || part.starts_with(".+") {
+            timestamp.repeater = Some(part.to_string());
+        }
+        // Day names are informational only
+    }
+
+    Some(timestamp)
+}
+
+/// Dates with scheduled or deadline blocks, for calendar markers
+pub fn planned_dates(blocks: &HashMap<String, Block>) -> (Vec<NaiveDate>, Vec<NaiveDate>) {
+    let mut scheduled = Vec::new();
+    let mut deadlines = Vec::new();
+
+    for block in blocks.values() {
+        let planning = parse_planning(&block.content);
+        if let Some(ts) = planning.scheduled {
+            scheduled.push(ts.date);
+        }
+        if let Some(ts) = planning.deadline {
+            deadlines.push(ts.date);
+        }
+    }
+
+    (scheduled, deadlines)
+}
+
+/// What a natural-language date being typed into a block will become
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateEntryKind {
+    /// `SCHEDULED: next friday`
+    Scheduled,
+    /// `DEADLINE: in 3 days`
+    Deadline,
+    /// `/date oct 20`, replaced by a journal page link
+    Link,
+}
+
+/// A natural-language date phrase awaiting resolution in block content
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateEntry {
+    pub kind: DateEntryKind,
+    pub phrase: String,
+    /// Byte range of the text to replace
+    pub range: std::ops::Range<usize>,
+}
+
+/// Find the last unresolved date phrase: a planning keyword followed by
+/// something other than an org timestamp, or a trailing `/date` command
+pub fn find_date_entry(content: &str) -> Option<DateEntry> {
+    let planning = Regex::new(r"(?m)^[ \t]*(SCHEDULED|DEADLINE):[ \t]*([^<\s][^\n]*)$").ok()?;
+    let command = Regex::new(r"(?m)(?:^|\s)(/date[ \t]+([^\n]+))$").ok()?;
+
+    let from_planning = planning.captures_iter(content).last().and_then(|cap| {
+        let kind = match cap.get(1)?.as_str() {
+            "SCHEDULED" => DateEntryKind::Scheduled,
+            _ => DateEntryKind::Deadline,
+        };
+        let whole = cap.get(0)?;
+        let start = whole.start() + whole.as_str().len() - whole.as_str().trim_start().len();
+        Some(DateEntry { kind, phrase: cap.get(2)?.as_str().trim().to_string(), range: start..whole.end() })
+    });
+    let from_command = command.captures_iter(content).last().and_then(|cap| {
+        let whole = cap.get(1)?;
+        Some(DateEntry {
+            kind: DateEntryKind::Link,
+            phrase: cap.get(2)?.as_str().trim().to_string(),
+            range: whole.range(),
+        })
+    });
+
+    match (from_planning, from_command) {
+        (Some(a), Some(b)) => Some(if a.range.start > b.range.start { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Replace a date entry with its resolved form: an org timestamp for
+/// planning lines, or a link to `journal_title` for `/date`
+pub fn apply_date_entry(
+    content: &str,
+    entry: &DateEntry,
+    date: NaiveDate,
+    time: Option<NaiveTime>,
+    journal_title: &str,
+) -> String {
+    let timestamp = Timestamp { date, time, repeater: None };
+    let replacement = match entry.kind {
+        DateEntryKind::Scheduled => format!("SCHEDULED: {}", timestamp.to_org()),
+        DateEntryKind::Deadline => format!("DEADLINE: {}", timestamp.to_org()),
+        DateEntryKind::Link => format!("[[{}]]", journal_title),
+    };
+
+    let mut out = String::with_capacity(content.len() + replacement.len());
+    out.push_str(&content[..entry.range.start]);
+    out.push_str(&replacement);
+    out.push_str(&content[entry.range.end..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn next(cookie: &str, date: NaiveDate, today: NaiveDate) -> Option<NaiveDate> {
+        Repeater::parse(cookie)?.next_date(date, today)
+    }
+
+    #[test]
+    fn parses_repeater_cookies() {
+        assert_eq!(Repeater::parse("+1w"), Some(Repeater { kind: RepeatKind::Cumulate, amount: 1, unit: RepeatUnit::Week }));
+        assert_eq!(Repeater::parse("++2d"), Some(Repeater { kind: RepeatKind::CatchUp, amount: 2, unit: RepeatUnit::Day }));
+        assert_eq!(Repeater::parse(".+3m"), Some(Repeater { kind: RepeatKind::Restart, amount: 3, unit: RepeatUnit::Month }));
+        assert_eq!(Repeater::parse("+0d"), None);
+        assert_eq!(Repeater::parse("+1h"), None);
+        assert_eq!(Repeater::parse("1d"), None);
+    }
+
+    #[test]
+    fn cumulate_shifts_once_even_when_behind() {
+        let today = ymd(2026, 3, 20);
+        assert_eq!(next("+1w", ymd(2026, 3, 2), today), Some(ymd(2026, 3, 9)));
+        assert_eq!(next("+1m", ymd(2026, 1, 31), today), Some(ymd(2026, 2, 28)));
+    }
+
+    #[test]
+    fn catch_up_moves_past_today_keeping_the_weekday() {
+        // Mondays, several weeks behind
+        let today = ymd(2026, 3, 20);
+        assert_eq!(next("++1w", ymd(2026, 2, 2), today), Some(ymd(2026, 3, 23)));
+        // Completing on the due day still moves to the next occurrence
+        assert_eq!(next("++1w", ymd(2026, 3, 16), ymd(2026, 3, 23)), Some(ymd(2026, 3, 30)));
+        // Already in the future: one step
+        assert_eq!(next("++1d", ymd(2026, 3, 25), today), Some(ymd(2026, 3, 26)));
+        assert_eq!(next("++1y", ymd(2020, 2, 29), today), Some(ymd(2027, 2, 28)));
+    }
+
+    #[test]
+    fn restart_counts_from_completion() {
+        let today = ymd(2026, 3, 20);
+        assert_eq!(next(".+2d", ymd(2026, 3, 2), today), Some(ymd(2026, 3, 22)));
+        assert_eq!(next(".+1m", ymd(2025, 12, 1), today), Some(ymd(2026, 4, 20)));
+    }
+
+    #[test]
+    fn huge_repeats_give_no_occurrence() {
+        let today = ymd(2026, 3, 20);
+        for cookie in ["+99999999d", "++99999999w", ".+4294967295y", "+4294967295m"] {
+            assert_eq!(next(cookie, ymd(2026, 1, 1), today), None, "{}", cookie);
+        }
+        let timestamp = parse_timestamp("2026-01-01 Thu +99999999d").unwrap();
+        assert_eq!(timestamp.next_occurrence(today), None);
+    }
+
+    #[test]
+    fn completing_a_recurring_task_advances_it() {
+        let now = ymd(2026, 3, 20).and_hms_opt(18, 30, 0).unwrap();
+        let done = complete_task("TODO review\nSCHEDULED: <2026-03-16 Mon ++1w>", now);
+        assert!(done.starts_with("TODO review\nSCHEDULED: <2026-03-23 Mon ++1w>"));
+        assert!(done.contains("completed:: 2026-03-20 18:30"));
+
+        // An out-of-range repeat leaves the date alone instead of panicking
+        let stuck = complete_task("TODO review\nSCHEDULED: <2026-01-01 Thu +99999999d>", now);
+        assert!(stuck.contains("SCHEDULED: <2026-01-01 Thu +99999999d>"));
+
+        assert_eq!(complete_task("TODO once", now), "DONE once");
+    }
+}
@@ -0,0 +1,319 @@
+use chrono::NaiveDateTime;
+use regex::Regex;
+use std::ops::Range;
+use std::rc::Rc;
+use crate::markdown::split_properties;
+use crate::slash::{SlashAction, SlashCommand, SlashRegistry};
+use crate::store::{AppState, Block};
+use crate::utils::generate_id;
+
+/// Block property naming a template: `template:: meeting notes`
+pub const TEMPLATE_PROPERTY: &str = "template";
+/// Root property to insert the marked block itself, not just its children
+pub const INCLUDING_PARENT_PROPERTY: &str = "template-including-parent";
+/// Root property to also copy the root's `tags::` and other properties onto
+/// the target page
+pub const PAGE_PROPERTIES_PROPERTY: &str = "template-page-properties";
+
+/// Properties that configure a template rather than belong to its content
+const TEMPLATE_PROPERTIES: [&str; 3] = [TEMPLATE_PROPERTY, INCLUDING_PARENT_PROPERTY, PAGE_PROPERTIES_PROPERTY];
+
+/// A block tree that can be instantiated
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub block_id: String,
+}
+
+/// Values for `{{date}}`, `{{time}}` and `{{page}}` placeholders
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateContext {
+    pub now: NaiveDateTime,
+    /// Title of the page the template is inserted into
+    pub page: String,
+    /// Journal title format used for `{{date}}`
+    pub date_format: String,
+}
+
+impl TemplateContext {
+    /// Placeholder values for inserting into `page_id` now
+    pub fn for_page(state: &AppState, page_id: &str) -> Self {
+        Self {
+            now: crate::journal::now(),
+            page: state.pages.get(page_id).map(|p| p.title.clone()).unwrap_or_default(),
+            date_format: state.journal_config.date_format.clone(),
+        }
+    }
+}
+
+/// Result of inserting a template
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateInsert {
+    /// Top-level blocks created
+    pub blocks: Vec<String>,
+    /// Block that held `{{cursor}}`, to open for editing
+    pub cursor_block: Option<String>,
+}
+
+/// All blocks marked `template:: name`, sorted by name
+pub fn find_templates(state: &AppState) -> Vec<TemplateInfo> {
+    let mut templates: Vec<TemplateInfo> = state.blocks.values()
+        .filter_map(|block| {
+            let (properties, _) = split_properties(&block.content);
+            properties
+                .into_iter()
+                .find(|(key, _)| key == TEMPLATE_PROPERTY)
+                .map(|(_, name)| TemplateInfo { name: name.trim().to_string(), block_id: block.id.clone() })
+        })
+        .filter(|t| !t.name.is_empty())
+        .collect();
+    templates.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    templates
+}
+
+/// Look up a template by name, ignoring case
+pub fn find_template(state: &AppState, name: &str) -> Option<TemplateInfo> {
+    let name = name.trim().to_lowercase();
+    find_templates(state).into_iter().find(|t| t.name.to_lowercase() == name)
+}
+
+/// Replace template placeholders; returns the text and whether it held `{{cursor}}`
+pub fn substitute(text: &str, ctx: &TemplateContext) -> (String, bool) {
+    let has_cursor = text.contains("{{cursor}}");
+    let date_title = crate::utils::get_date_title(&ctx.now.date(), &ctx.date_format)
+        .unwrap_or_else(|| ctx.now.format(crate::journal::DEFAULT_DATE_FORMAT).to_string());
+    let text = text
+        .replace("{{date}}", &date_title)
+        .replace("{{time}}", &ctx.now.format("%H:%M").to_string())
+        .replace("{{page}}", &ctx.page)
+        .replace("{{cursor}}", "");
+    (text, has_cursor)
+}
+
+/// Find a trailing `/template name` command typed into block content
+pub fn find_template_command(content: &str) -> Option<(String, Range<usize>)> {
+    let re = Regex::new(r"(?m)(?:^|\s)(/template[ \t]+([^\n]+))$").ok()?;
+    let cap = re.captures_iter(content).last()?;
+    Some((cap.get(2)?.as_str().trim().to_string(), cap.get(1)?.range()))
+}
+
+/// Offer one `/` menu entry per template that inserts it after the block
+pub fn register_slash_commands(registry: &mut SlashRegistry) {
+    registry.register_provider("templates", |state| {
+        find_templates(state)
+            .into_iter()
+            .map(|template| {
+                let name = template.name.clone();
+                SlashCommand::new(
+                    &format!("template:{}", template.block_id),
+                    &format!("Template: {}", name),
+                    "Insert this template after the block",
+                    "🧩",
+                    SlashAction::Run(Rc::new(move |state, target| {
+                        let _ = insert(state, &template, &target.page_id, Some(&target.block_id));
+                    })),
+                )
+                .with_keywords(&["template", &name])
+            })
+            .collect()
+    });
+}
+
+/// Instantiate a template and open its `{{cursor}}` block, or its first
+/// block, for editing
+pub fn insert(state: &mut AppState, template: &TemplateInfo, page_id: &str, after: Option<&str>) -> Result<TemplateInsert, String> {
+    let ctx = TemplateContext::for_page(state, page_id);
+    let insert = instantiate(state, template, page_id, after, &ctx)?;
+    state.pending_edit = insert.cursor_block.clone();
+    state.current_block_id = insert.cursor_block.clone().or_else(|| insert.blocks.first().cloned());
+    Ok(insert)
+}
+
+/// Clone a template into a page with fresh block ids.
+///
+/// Children of the marked block are inserted after `after` (or at the end of
+/// the page); with `template-including-parent:: true` the marked block is
+/// inserted too. With `template-page-properties:: true` the root's `tags::`
+/// are added to the page tags and its other properties are copied onto the page.
+pub fn instantiate(
+    state: &mut AppState,
+    template: &TemplateInfo,
+    page_id: &str,
+    after: Option<&str>,
+    ctx: &TemplateContext,
+) -> Result<TemplateInsert, String> {
+    let root = state.blocks.get(&template.block_id).cloned()
+        .ok_or_else(|| format!("Template \"{}\" no longer exists", template.name))?;
+    if !state.pages.contains_key(page_id) {
+        return Err("Page not found".to_string());
+    }
+
+    let (root_properties, _) = split_properties(&root.content);
+    let enabled = |name: &str| root_properties.iter().any(|(key, value)| key == name && value.trim() == "true");
+    let including_parent = enabled(INCLUDING_PARENT_PROPERTY);
+    let page_properties = enabled(PAGE_PROPERTIES_PROPERTY);
+
+    let sources = if including_parent { vec![root.id.clone()] } else { root.children.clone() };
+    let parent_id = after.and_then(|id| state.blocks.get(id)).and_then(|b| b.parent_id.clone());
+
+    let mut insert = TemplateInsert::default();
+    for source in sources {
+        if let Some(id) = clone_subtree(state, &source, parent_id.clone(), ctx, &mut insert.cursor_block) {
+            insert.blocks.push(id);
+        }
+    }
+
+    // Place the new top-level blocks among their siblings
+    let siblings = match &parent_id {
+        Some(pid) => state.blocks.get_mut(pid).map(|b| &mut b.children),
+        None => state.pages.get_mut(page_id).map(|p| &mut p.blocks),
+    };
+    if let Some(siblings) = siblings {
+        let position = after
+            .and_then(|id| siblings.iter().position(|s| s == id))
+            .map(|i| i + 1)
+            .unwrap_or(siblings.len());
+        for (offset, id) in insert.blocks.iter().enumerate() {
+            siblings.insert(position + offset, id.clone());
+        }
+    }
+
+    if page_properties {
+        apply_page_properties(state, page_id, &root_properties);
+    }
+
+    Ok(insert)
+}
+
+fn clone_subtree(
+    state: &mut AppState,
+    source_id: &str,
+    parent_id: Option<String>,
+    ctx: &TemplateContext,
+    cursor_block: &mut Option<String>,
+) -> Option<String> {
+    let source = state.blocks.get(source_id).cloned()?;
+    let id = generate_id();
+    let (content, has_cursor) = substitute(&strip_template_properties(&source.content), ctx);
+    if has_cursor && cursor_block.is_none() {
+        *cursor_block = Some(id.clone());
+    }
+
+    let mut properties = source.properties.clone();
+    for key in TEMPLATE_PROPERTIES {
+        properties.remove(key);
+    }
+
+    state.blocks.insert(id.clone(), Block {
+        id: id.clone(),
+        content,
+        parent_id,
+        properties,
+        ..Default::default()
+    });
+
+    let children: Vec<String> = source.children.iter()
+        .filter_map(|child| clone_subtree(state, child, Some(id.clone()), ctx, cursor_block))
+        .collect();
+    if let Some(block) = state.blocks.get_mut(&id) {
+        block.children = children;
+    }
+
+    Some(id)
+}
+
+/// Drop `template::`, `template-including-parent::` and
+/// `template-page-properties::` lines
+fn strip_template_properties(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| {
+            let key = line.trim_start().split("::").next().unwrap_or_default().trim();
+            !(line.contains("::") && TEMPLATE_PROPERTIES.contains(&key))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn apply_page_properties(state: &mut AppState, page_id: &str, properties: &[(String, String)]) {
+    let page = match state.pages.get_mut(page_id) {
+        Some(page) => page,
+        None => return,
+    };
+
+    for (key, value) in properties {
+        match key.as_str() {
+            key if TEMPLATE_PROPERTIES.contains(&key) => {}
+            "tags" => {
+                for tag in value.split(',').map(|t| t.trim().trim_start_matches('#')) {
+                    let tag = tag.trim_start_matches("[[").trim_end_matches("]]");
+                    if !tag.is_empty() && !page.tags.iter().any(|t| t == tag) {
+                        page.tags.push(tag.to_string());
+                    }
+                }
+            }
+            _ => {
+                page.properties.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    page.updated_at = chrono::Utc::now();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> TemplateContext {
+        TemplateContext {
+            now: chrono::NaiveDate::from_ymd_opt(2026, 3, 9).unwrap().and_hms_opt(14, 5, 0).unwrap(),
+            page: "Weekly Sync".to_string(),
+            date_format: crate::journal::DEFAULT_DATE_FORMAT.to_string(),
+        }
+    }
+
+    /// A template page holding `root` with one `{{date}} {{cursor}}` child
+    fn setup(root: &str) -> (AppState, TemplateInfo, String) {
+        let mut state = AppState::default();
+        let templates = state.create_page("Templates");
+        state.insert_outline(&templates, &format!("- {}\n  - Notes for {{{{page}}}} on {{{{date}}}} {{{{cursor}}}}", root));
+        let target = state.create_page("Weekly Sync");
+        let template = find_template(&state, "meeting").unwrap();
+        (state, template, target)
+    }
+
+    #[test]
+    fn clones_children_with_fresh_ids_and_placeholders() {
+        let (mut state, template, target) = setup("template:: Meeting");
+        let insert = instantiate(&mut state, &template, &target, None, &ctx()).unwrap();
+
+        assert_eq!(insert.blocks.len(), 1);
+        assert_eq!(insert.cursor_block.as_deref(), Some(insert.blocks[0].as_str()));
+        let source = &state.blocks[&template.block_id].children[0];
+        assert_ne!(source, &insert.blocks[0]);
+        assert_eq!(state.blocks[&insert.blocks[0]].content, "Notes for Weekly Sync on 2026-03-09 ");
+        assert_eq!(state.pages[&target].blocks, insert.blocks);
+    }
+
+    #[test]
+    fn page_properties_are_opt_in() {
+        let (mut state, template, target) = setup("template:: Meeting\ntags:: meeting, [[team]]\ntype:: sync");
+        instantiate(&mut state, &template, &target, None, &ctx()).unwrap();
+        assert!(state.pages[&target].tags.is_empty());
+        assert!(state.pages[&target].properties.is_empty());
+
+        let (mut state, template, target) = setup("template:: Meeting\ntemplate-page-properties:: true\ntags:: meeting, [[team]]\ntype:: sync");
+        instantiate(&mut state, &template, &target, None, &ctx()).unwrap();
+        assert_eq!(state.pages[&target].tags, vec!["meeting", "team"]);
+        assert_eq!(state.pages[&target].properties.get("type").map(String::as_str), Some("sync"));
+        assert!(!state.pages[&target].properties.contains_key(PAGE_PROPERTIES_PROPERTY));
+    }
+
+    #[test]
+    fn including_parent_strips_template_properties() {
+        let (mut state, template, target) = setup("Agenda\ntemplate:: Meeting\ntemplate-including-parent:: true");
+        let insert = instantiate(&mut state, &template, &target, None, &ctx()).unwrap();
+        assert_eq!(state.blocks[&insert.blocks[0]].content, "Agenda");
+        assert_eq!(state.blocks[&insert.blocks[0]].children.len(), 1);
+    }
+}
@@ -0,0 +1,478 @@
+use chrono::{Datelike, Days, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+/// Date conventions of the user's locale
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DateLocale {
+    /// `03/04` means 3 April rather than March 4
+    pub day_first: bool,
+}
+
+impl DateLocale {
+    /// Build from a BCP 47 language tag such as `en-US` or `de-DE`
+    pub fn from_language(tag: &str) -> Self {
+        let tag = tag.to_lowercase();
+        // Month-first numeric dates are mostly a US convention
+        let month_first = tag == "en" || ["en-us", "en-ph", "en-ca", "es-us"].iter().any(|t| tag.starts_with(t));
+        Self { day_first: !month_first }
+    }
+
+    /// Detect from the browser language. Reads the DOM, so the UI calls
+    /// this once at startup and stores it in `AppState::date_locale`.
+    #[cfg(feature = "web")]
+    pub fn detect() -> Self {
+        web_sys::window()
+            .and_then(|w| w.navigator().language())
+            .map(|lang| Self::from_language(&lang))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(feature = "web"))]
+    pub fn detect() -> Self {
+        Self::default()
+    }
+}
+
+/// One possible reading of a date phrase
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateCandidate {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+    /// Human-readable description shown when choices are offered
+    pub label: String,
+}
+
+impl DateCandidate {
+    fn new(date: NaiveDate, time: Option<NaiveTime>) -> Self {
+        let mut label = date.format("%a, %b %-d %Y").to_string();
+        if let Some(time) = time {
+            label.push_str(&format!(" {}", time.format("%H:%M")));
+        }
+        Self { date, time, label }
+    }
+}
+
+/// Parse a natural-language date such as `today`, `next monday`, `in 3 days`,
+/// `Oct 20`, `03/04` or `2026-11-01 14:00`.
+///
+/// Returns no candidates when the input is not a date, one when it is
+/// unambiguous, and several (most likely first) when the user should choose.
+pub fn parse_natural_date(input: &str, now: NaiveDateTime, locale: &DateLocale) -> Vec<DateCandidate> {
+    let text = input.trim().to_lowercase().replace(',', " ");
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    // Peel a trailing time ("14:00", "2pm", "at 9:30am")
+    let (date_words, time) = split_time(&words);
+    let today = now.date();
+
+    let dates = if date_words.is_empty() {
+        // A bare time means today
+        if time.is_some() { vec![today] } else { Vec::new() }
+    } else {
+        parse_date_words(&date_words, today, locale)
+    };
+
+    dates.into_iter().map(|date| DateCandidate::new(date, time)).collect()
+}
+
+/// Resolve a date-like page link such as `2026-10-16`, `today` or `next friday`
+/// relative to `today`. Returns `None` for ordinary page titles or ambiguous input.
+pub fn resolve_date_link(text: &str, today: NaiveDate, locale: &DateLocale) -> Option<NaiveDate> {
+    let now = today.and_time(NaiveTime::MIN);
+    match parse_natural_date(text, now, locale).as_slice() {
+        [candidate] if candidate.time.is_none() => Some(candidate.date),
+        _ => None,
+    }
+}
+
+fn split_time<'a>(words: &[&'a str]) -> (Vec<&'a str>, Option<NaiveTime>) {
+    let mut words = words.to_vec();
+
+    // "2 pm" is written as two words
+    if words.len() >= 2 {
+        let last = words[words.len() - 1];
+        if last == "am" || last == "pm" {
+            if let Some(time) = parse_time(&format!("{}{}", words[words.len() - 2], last)) {
+                words.truncate(words.len() - 2);
+                if words.last() == Some(&"at") {
+                    words.pop();
+                }
+                return (words, Some(time));
+            }
+        }
+    }
+
+    if let Some(time) = words.last().and_then(|w| parse_time(w)) {
+        words.pop();
+        if words.last() == Some(&"at") {
+            words.pop();
+        }
+        return (words, Some(time));
+    }
+
+    (words, None)
+}
+
+/// Parse `14:00`, `9:30am`, `2pm`, `noon` or `midnight`
+pub fn parse_time(text: &str) -> Option<NaiveTime> {
+    match text {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (body, offset) = if let Some(body) = text.strip_suffix("am") {
+        (body, Some(0))
+    } else if let Some(body) = text.strip_suffix("pm") {
+        (body, Some(12))
+    } else {
+        (text, None)
+    };
+
+    let (hour, minute) = match body.split_once(':') {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        // A bare number is only a time with am/pm ("3" alone could be a day)
+        None if offset.is_some() => (body.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+
+    let hour = match offset {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn parse_date_words(words: &[&str], today: NaiveDate, locale: &DateLocale) -> Vec<NaiveDate> {
+    match words {
+        ["today"] | ["now"] | ["tonight"] => vec![today],
+        ["tomorrow"] | ["tmr"] => vec![today + Duration::days(1)],
+        ["yesterday"] => vec![today - Duration::days(1)],
+        ["next", "week"] => vec![today + Duration::weeks(1)],
+        ["next", "month"] => add_months(today, 1).into_iter().collect(),
+        ["next", "year"] => add_months(today, 12).into_iter().collect(),
+        ["next", day] => parse_weekday(day).map(|wd| next_weekday(today, wd)).into_iter().collect(),
+        ["last", day] => parse_weekday(day).map(|wd| previous_weekday(today, wd)).into_iter().collect(),
+        ["this", day] => parse_weekday(day).map(|wd| this_weekday(today, wd)).into_iter().collect(),
+        ["in", amount, unit] => offset(today, amount, unit, 1),
+        [amount, unit, "ago"] => offset(today, amount, unit, -1),
+        [single] => parse_single(single, today, locale),
+        _ => parse_month_day(words, today),
+    }
+}
+
+/// Single-token dates: weekday names, ISO dates and numeric `a/b[/c]` dates
+fn parse_single(word: &str, today: NaiveDate, locale: &DateLocale) -> Vec<NaiveDate> {
+    if let Some(weekday) = parse_weekday(word) {
+        // A bare weekday means the upcoming one (today counts)
+        return vec![upcoming_weekday(today, weekday)];
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return vec![date];
+    }
+
+    let parts: Vec<&str> = word.split(['/', '.']).collect();
+    let numbers: Option<Vec<u32>> = parts.iter().map(|p| p.parse().ok()).collect();
+    let numbers = match numbers {
+        Some(numbers) if numbers.len() == 2 || numbers.len() == 3 => numbers,
+        _ => return Vec::new(),
+    };
+
+    let (a, b) = (numbers[0], numbers[1]);
+    let year = numbers.get(2).map(|y| if *y < 100 { 2000 + *y as i32 } else { *y as i32 });
+
+    // Locale order first, the swapped reading second when it is also valid
+    let orders = if locale.day_first { [(b, a), (a, b)] } else { [(a, b), (b, a)] };
+    let mut dates = Vec::new();
+    for (month, day) in orders {
+        let date = match year {
+            Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+            None => upcoming_month_day(today, month, day),
+        };
+        if let Some(date) = date {
+            if !dates.contains(&date) {
+                dates.push(date);
+            }
+        }
+    }
+    dates
+}
+
+/// `Oct 20`, `20 October`, `October 20th 2027`
+fn parse_month_day(words: &[&str], today: NaiveDate) -> Vec<NaiveDate> {
+    let (month, rest): (u32, Vec<&str>) = if let Some(month) = parse_month(words[0]) {
+        (month, words[1..].to_vec())
+    } else if let Some(month) = words.get(1).and_then(|w| parse_month(w)) {
+        let mut rest = vec![words[0]];
+        rest.extend_from_slice(&words[2..]);
+        (month, rest)
+    } else {
+        return Vec::new();
+    };
+
+    let day = match rest.first().and_then(|d| parse_day(d)) {
+        Some(day) => day,
+        None => return Vec::new(),
+    };
+    let year = rest.get(1).and_then(|y| y.parse::<i32>().ok());
+    if rest.len() > 2 || (rest.len() == 2 && year.is_none()) {
+        return Vec::new();
+    }
+
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day).into_iter().collect(),
+        None => {
+            let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+            let next_year = NaiveDate::from_ymd_opt(today.year() + 1, month, day);
+            match (this_year, next_year) {
+                // Already past this year: offer next year first, this year second
+                (Some(this), Some(next)) if this < today => vec![next, this],
+                (Some(this), _) => vec![this],
+                (None, Some(next)) => vec![next],
+                (None, None) => Vec::new(),
+            }
+        }
+    }
+}
+
+fn parse_day(text: &str) -> Option<u32> {
+    let digits = text.trim_end_matches(|c: char| c.is_alphabetic());
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn parse_month(text: &str) -> Option<u32> {
+    let month = match text.trim_end_matches('.') {
+        "jan" | "january" => 1,
+        "feb" | "february" => 2,
+        "mar" | "march" => 3,
+        "apr" | "april" => 4,
+        "may" => 5,
+        "jun" | "june" => 6,
+        "jul" | "july" => 7,
+        "aug" | "august" => 8,
+        "sep" | "sept" | "september" => 9,
+        "oct" | "october" => 10,
+        "nov" | "november" => 11,
+        "dec" | "december" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+fn offset(today: NaiveDate, amount: &str, unit: &str, sign: i64) -> Vec<NaiveDate> {
+    let amount: i64 = match amount {
+        "a" | "an" | "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        n => match n.parse() {
+            Ok(n) => n,
+            Err(_) => return Vec::new(),
+        },
+    };
+
+    // Amounts come straight from user text, so every step is checked and
+    // an out-of-range date yields no candidate
+    let amount = sign.checked_mul(amount);
+    let date = match unit.trim_end_matches('s') {
+        "day" | "d" => amount.and_then(|days| add_days(today, days)),
+        "week" | "w" => amount.and_then(|n| n.checked_mul(7)).and_then(|days| add_days(today, days)),
+        "month" | "m" => amount.and_then(|months| add_months(today, months)),
+        "year" | "y" => amount.and_then(|n| n.checked_mul(12)).and_then(|months| add_months(today, months)),
+        _ => None,
+    };
+    date.into_iter().collect()
+}
+
+/// Add (or subtract) days; `None` outside the supported date range
+pub fn add_days(date: NaiveDate, days: i64) -> Option<NaiveDate> {
+    let magnitude = Days::new(days.unsigned_abs());
+    if days >= 0 {
+        date.checked_add_days(magnitude)
+    } else {
+        date.checked_sub_days(magnitude)
+    }
+}
+
+/// Add (or subtract) calendar months, clamping to the end of shorter months
+pub fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let magnitude = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+    if months >= 0 {
+        date.checked_add_months(magnitude)
+    } else {
+        date.checked_sub_months(magnitude)
+    }
+}
+
+/// The next occurrence of month/day on or after `today`
+fn upcoming_month_day(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    match NaiveDate::from_ymd_opt(today.year(), month, day) {
+        Some(date) if date >= today => Some(date),
+        Some(_) => NaiveDate::from_ymd_opt(today.year() + 1, month, day),
+        None => None,
+    }
+}
+
+/// Parse an English weekday name or abbreviation
+pub fn parse_weekday(text: &str) -> Option<Weekday> {
+    match text.trim().to_lowercase().as_str() {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thur" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// The given weekday on or after `from`
+pub fn upcoming_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let diff = (weekday.num_days_from_monday() as i64 - from.weekday().num_days_from_monday() as i64 + 7) % 7;
+    from + Duration::days(diff)
+}
+
+/// The first given weekday strictly after `from`
+pub fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let diff = (weekday.num_days_from_monday() as i64 - from.weekday().num_days_from_monday() as i64 + 7) % 7;
+    from + Duration::days(if diff == 0 { 7 } else { diff })
+}
+
+/// The last given weekday strictly before `from`
+pub fn previous_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let diff = (from.weekday().num_days_from_monday() as i64 - weekday.num_days_from_monday() as i64 + 7) % 7;
+    from - Duration::days(if diff == 0 { 7 } else { diff })
+}
+
+/// The given weekday in the current Monday-Sunday week
+pub fn this_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let monday = from - Duration::days(from.weekday().num_days_from_monday() as i64);
+    monday + Duration::days(weekday.num_days_from_monday() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 1, 31).unwrap()
+    }
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn hm(h: u32, m: u32) -> Option<NaiveTime> {
+        NaiveTime::from_hms_opt(h, m, 0)
+    }
+
+    fn candidates(input: &str, locale: &DateLocale) -> Vec<(NaiveDate, Option<NaiveTime>)> {
+        parse_natural_date(input, today().and_time(NaiveTime::MIN), locale)
+            .into_iter()
+            .map(|c| (c.date, c.time))
+            .collect()
+    }
+
+    fn dates(input: &str) -> Vec<NaiveDate> {
+        candidates(input, &DateLocale::default()).into_iter().map(|(date, _)| date).collect()
+    }
+
+    #[test]
+    fn weekdays() {
+        // 2026-01-31 is a Saturday
+        assert_eq!(dates("next friday"), vec![ymd(2026, 2, 6)]);
+        assert_eq!(dates("Friday"), vec![ymd(2026, 2, 6)]);
+        assert_eq!(dates("saturday"), vec![today()]);
+        assert_eq!(dates("next sat"), vec![ymd(2026, 2, 7)]);
+        assert_eq!(dates("last mon"), vec![ymd(2026, 1, 26)]);
+        assert_eq!(dates("next fryday"), vec![]);
+    }
+
+    #[test]
+    fn month_names() {
+        assert_eq!(dates("Oct 20"), vec![ymd(2026, 10, 20)]);
+        assert_eq!(dates("20 October 2027"), vec![ymd(2027, 10, 20)]);
+        assert_eq!(dates("december 3rd"), vec![ymd(2026, 12, 3)]);
+        // Already past this year: next year first
+        assert_eq!(dates("Jan 5"), vec![ymd(2027, 1, 5), ymd(2026, 1, 5)]);
+        assert_eq!(dates("Feb 30"), vec![]);
+    }
+
+    #[test]
+    fn numeric_dates_follow_the_locale() {
+        let month_first = DateLocale::from_language("en-US");
+        let day_first = DateLocale::from_language("de-DE");
+        assert!(!month_first.day_first);
+        assert!(day_first.day_first);
+        assert!(DateLocale::from_language("en-GB").day_first);
+
+        // Ambiguous: both readings, the locale's first
+        let march_4 = (ymd(2026, 3, 4), None);
+        let april_3 = (ymd(2026, 4, 3), None);
+        assert_eq!(candidates("03/04", &month_first), vec![march_4, april_3]);
+        assert_eq!(candidates("03/04", &day_first), vec![april_3, march_4]);
+        assert_eq!(resolve_date_link("03/04", today(), &month_first), None);
+
+        // Only one valid reading
+        assert_eq!(candidates("13/04", &month_first), vec![(ymd(2026, 4, 13), None)]);
+        assert_eq!(resolve_date_link("13/04", today(), &month_first), Some(ymd(2026, 4, 13)));
+        assert_eq!(candidates("4.3.27", &day_first)[0], (ymd(2027, 3, 4), None));
+    }
+
+    #[test]
+    fn times() {
+        let locale = DateLocale::default();
+        assert_eq!(candidates("2pm", &locale), vec![(today(), hm(14, 0))]);
+        assert_eq!(candidates("tomorrow at 9:30am", &locale), vec![(ymd(2026, 2, 1), hm(9, 30))]);
+        assert_eq!(candidates("next friday 2 pm", &locale), vec![(ymd(2026, 2, 6), hm(14, 0))]);
+        assert_eq!(candidates("today 12am", &locale), vec![(today(), hm(0, 0))]);
+        assert_eq!(candidates("noon", &locale), vec![(today(), hm(12, 0))]);
+        assert_eq!(candidates("13pm", &locale), vec![]);
+        // A time makes a link a reminder, not a journal page
+        assert_eq!(resolve_date_link("tomorrow 9am", today(), &locale), None);
+    }
+
+    #[test]
+    fn iso_dates() {
+        let locale = DateLocale::default();
+        assert_eq!(dates("2026-11-01"), vec![ymd(2026, 11, 1)]);
+        assert_eq!(candidates("2026-11-01 14:00", &locale), vec![(ymd(2026, 11, 1), hm(14, 0))]);
+        assert_eq!(dates("2026-02-30"), vec![]);
+        assert_eq!(resolve_date_link("2026-10-16", today(), &locale), Some(ymd(2026, 10, 16)));
+        assert_eq!(resolve_date_link("Project Plan", today(), &locale), None);
+    }
+
+    #[test]
+    fn relative_offsets() {
+        assert_eq!(dates("in 3 days"), vec![ymd(2026, 2, 3)]);
+        assert_eq!(dates("2 weeks ago"), vec![ymd(2026, 1, 17)]);
+        assert_eq!(dates("in a month"), vec![ymd(2026, 2, 28)]);
+        assert_eq!(dates("in 2 years"), vec![ymd(2028, 1, 31)]);
+    }
+
+    #[test]
+    fn huge_offsets_give_no_candidate() {
+        for input in [
+            "in 100000000 days",
+            "100000000 days ago",
+            "in 9223372036854775807 weeks",
+            "in 5000000000 months",
+            "in 9223372036854775807 years",
+            "9223372036854775807 years ago",
+        ] {
+            assert!(dates(input).is_empty(), "{}", input);
+        }
+        assert_eq!(resolve_date_link("in 100000000 days", today(), &DateLocale::default()), None);
+    }
+
+    #[test]
+    fn add_months_clamps_and_checks() {
+        assert_eq!(add_months(today(), -2), NaiveDate::from_ymd_opt(2025, 11, 30));
+        assert_eq!(add_months(today(), i64::MIN), None);
+        assert_eq!(add_days(today(), i64::MAX), None);
+    }
+}
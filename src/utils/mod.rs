@@ -1,6 +1,10 @@
 use regex::Regex;
 use std::collections::HashMap;
-use chrono::{DateTime, Utc, TimeZone};
+use std::fmt::Write;
+use chrono::{DateTime, Datelike, Utc, TimeZone};
+use chrono::format::{Item, StrftimeItems};
+
+pub mod dates;
 
 /// Extract wikilinks from text
 /// Returns a vector of (link_text, alias) tuples
@@ -25,17 +29,10 @@ pub fn extract_wikilinks(text: &str) -> Vec<(String, Option<String>)> {
 
 /// Extract tags from text
 pub fn extract_tags(text: &str) -> Vec<String> {
-    let mut tags = Vec::new();
-    
-    if let Ok(re) = Regex::new(r"#([a-zA-Z0-9_-]+)") {
-        for cap in re.captures_iter(text) {
-            if let Some(tag) = cap.get(1) {
-                tags.push(format!("#{}", tag.as_str()));
-            }
-        }
-    }
-    
-    tags
+    crate::markdown::collect_tags(&crate::markdown::parse_inline(text))
+        .into_iter()
+        .map(|tag| format!("#{}", tag))
+        .collect()
 }
 
 /// Parse properties from a block
@@ -106,58 +103,42 @@ pub fn slugify(title: &str) -> String {
 /// Parse markdown-style formatting
 /// Returns HTML string
 pub fn parse_markdown(text: &str) -> String {
-    let mut result = escape_html(text);
-    
-    // Bold
-    if let Ok(re) = Regex::new(r"\*\*(.+?)\*\*") {
-        result = re.replace_all(&result, "<strong>$1</strong>").into_owned();
-    }
-    
-    // Italic
-    if let Ok(re) = Regex::new(r"\*(.+?)\*") {
-        result = re.replace_all(&result, "<em>$1</em>").into_owned();
-    }
-    
-    // Code inline
-    if let Ok(re) = Regex::new(r"`(.+?)`") {
-        result = re.replace_all(&result, "<code class=\"bg-obsidian-100 dark:bg-obsidian-800 px-1 rounded\">$1</code>").into_owned();
-    }
-    
-    // Strikethrough
-    if let Ok(re) = Regex::new(r"~~(.+?)~~") {
-        result = re.replace_all(&result, "<del>$1</del>").into_owned();
-    }
-    
-    // Highlight
-    if let Ok(re) = Regex::new(r"==(.+?)==") {
-        result = re.replace_all(&result, "<mark>$1</mark>").into_owned();
-    }
-    
-    result
+    let nodes = crate::markdown::parse_blocks(text);
+    crate::markdown::block::to_html(&nodes, &HashMap::new())
 }
 
-/// Escape HTML special characters
-fn escape_html(text: &str) -> String {
-    text.replace("&", "&amp;")
-        .replace("<", "&lt;")
-        .replace(">", "&gt;")
-        .replace("\"", "&quot;")
-        .replace("'", "&#39;")
+/// Get daily note title for today.
+/// Returns `None` when `format` is not a valid strftime string.
+pub fn get_today_title(format: &str) -> Option<String> {
+    let items = strftime_items(format)?;
+    let mut title = String::new();
+    write!(title, "{}", chrono::Local::now().format_with_items(items)).ok()?;
+    Some(title)
 }
 
-/// Get daily note title for today
-pub fn get_today_title() -> String {
-    let now = chrono::Local::now();
-    now.format("%Y-%m-%d").to_string()
+/// Get daily note title for a specific date.
+/// Returns `None` when `format` is not a valid strftime string or asks
+/// for a time of day.
+pub fn get_date_title(date: &chrono::NaiveDate, format: &str) -> Option<String> {
+    let items = strftime_items(format)?;
+    let mut title = String::new();
+    write!(title, "{}", date.format_with_items(items)).ok()?;
+    Some(title)
 }
 
-/// Get daily note title for a specific date
-pub fn get_date_title(date: &chrono::DateTime<chrono::FixedOffset>) -> String {
-    date.format("%Y-%m-%d").to_string()
+/// Parsed strftime items; `None` if any specifier is invalid, since
+/// formatting those with `to_string()` panics
+fn strftime_items(format: &str) -> Option<StrftimeItems<'_>> {
+    let items = StrftimeItems::new(format);
+    if items.clone().any(|item| matches!(item, Item::Error)) {
+        None
+    } else {
+        Some(items)
+    }
 }
 
 /// Generate week dates for calendar view
-pub fn get_week_dates() -> Vec<(String, String)> {
+pub fn get_week_dates() -> Vec<(chrono::NaiveDate, String)> {
     let now = chrono::Local::now();
     let today = now.date_naive();
     
@@ -168,15 +149,34 @@ pub fn get_week_dates() -> Vec<(String, String)> {
     
     for i in 0..7 {
         let date = start + chrono::Duration::days(i);
-        dates.push((
-            date.format("%Y-%m-%d").to_string(),
-            date.format("%a %d").to_string(),
-        ));
+        dates.push((date, date.format("%a %d").to_string()));
     }
     
     dates
 }
 
+/// Generate the weeks (Monday first) covering a month for the calendar view
+pub fn get_month_grid(year: i32, month: u32) -> Vec<Vec<chrono::NaiveDate>> {
+    let first = match chrono::NaiveDate::from_ymd_opt(year, month, 1) {
+        Some(date) => date,
+        None => return Vec::new(),
+    };
+    let start = first - chrono::Duration::days(first.weekday().num_days_from_monday() as i64);
+
+    let mut weeks = Vec::new();
+    let mut day = start;
+    loop {
+        let week: Vec<_> = (0..7).map(|i| day + chrono::Duration::days(i)).collect();
+        day = day + chrono::Duration::days(7);
+        weeks.push(week);
+        if day.month() != month {
+            break;
+        }
+    }
+
+    weeks
+}
+
 /// Search pages by title and content
 pub fn search_pages(
     query: &str,
@@ -287,6 +287,114 @@ pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
     Err("Window not available".to_string())
 }
 
+/// Copy text to clipboard through the desktop webview's clipboard API
+#[cfg(not(feature = "web"))]
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let text = serde_json::to_string(text).map_err(|e| e.to_string())?;
+    let _ = dioxus::document::eval(&format!("navigator.clipboard.writeText({})", text));
+    Ok(())
+}
+
+/// Wait for `ms` milliseconds without blocking the UI
+#[cfg(feature = "web")]
+pub async fn sleep(ms: u32) {
+    gloo_timers::future::TimeoutFuture::new(ms).await;
+}
+
+#[cfg(not(feature = "web"))]
+pub async fn sleep(ms: u32) {
+    tokio::time::sleep(std::time::Duration::from_millis(ms as u64)).await;
+}
+
+/// Milliseconds since the Unix epoch, for frame time budgets
+#[cfg(feature = "web")]
+pub fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(feature = "web"))]
+pub fn now_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64() * 1000.0)
+        .unwrap_or_default()
+}
+
+/// Move the caret of a textarea to a byte offset of its text
+#[cfg(feature = "web")]
+pub fn set_textarea_caret(element_id: &str, text: &str, offset: usize) {
+    use wasm_bindgen::JsCast;
+
+    let textarea = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(element_id))
+        .and_then(|e| e.dyn_into::<web_sys::HtmlTextAreaElement>().ok());
+
+    if let Some(textarea) = textarea {
+        // DOM selection offsets count UTF-16 code units
+        let position = text.get(..offset).unwrap_or(text).encode_utf16().count() as u32;
+        let _ = textarea.focus();
+        let _ = textarea.set_selection_range(position, position);
+    }
+}
+
+#[cfg(not(feature = "web"))]
+pub fn set_textarea_caret(_element_id: &str, _text: &str, _offset: usize) {}
+
+/// Byte offset of a textarea's caret within its text
+#[cfg(feature = "web")]
+pub fn textarea_caret(element_id: &str) -> Option<usize> {
+    use wasm_bindgen::JsCast;
+
+    let textarea = web_sys::window()?
+        .document()?
+        .get_element_by_id(element_id)?
+        .dyn_into::<web_sys::HtmlTextAreaElement>()
+        .ok()?;
+    let position = textarea.selection_start().ok()?? as usize;
+    let text = textarea.value();
+
+    // Convert from UTF-16 code units back to a byte offset
+    let mut units = 0;
+    for (offset, ch) in text.char_indices() {
+        if units >= position {
+            return Some(offset);
+        }
+        units += ch.len_utf16();
+    }
+    Some(text.len())
+}
+
+#[cfg(not(feature = "web"))]
+pub fn textarea_caret(_element_id: &str) -> Option<usize> {
+    None
+}
+
+/// Subsequence match score: higher for consecutive characters and matches
+/// at word starts; `None` when `query` is not a subsequence of `text`
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.to_lowercase().chars() {
+        let found = (position..text.len()).find(|&i| text[i] == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 3;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    // Prefer shorter labels for the same matches
+    Some(score * 10 - text.len() as i32)
+}
+
 /// Format file size
 pub fn format_file_size(bytes: usize) -> String {
     if bytes < 1024 {
@@ -297,3 +405,47 @@ pub fn format_file_size(bytes: usize) -> String {
         format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Weekday};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn month_grid_weeks_start_on_monday() {
+        // June 2026 starts on a Monday, so no days of May are shown
+        let june = get_month_grid(2026, 6);
+        assert_eq!(june.len(), 5);
+        assert_eq!(june[0][0], date(2026, 6, 1));
+        assert_eq!(june[4][6], date(2026, 7, 5));
+        assert!(june.iter().all(|week| week.len() == 7 && week[0].weekday() == Weekday::Mon));
+
+        // March 2026 starts on a Sunday, the last day of its first week
+        let march = get_month_grid(2026, 3);
+        assert_eq!(march.len(), 6);
+        assert_eq!(march[0][0], date(2026, 2, 23));
+        assert_eq!(march[0][6], date(2026, 3, 1));
+        assert_eq!(march[5][1], date(2026, 3, 31));
+    }
+
+    #[test]
+    fn month_grid_february() {
+        // February 2021 fills exactly four weeks; February 2026 needs five
+        let grid = get_month_grid(2021, 2);
+        assert_eq!(grid.len(), 4);
+        assert_eq!((grid[0][0], grid[3][6]), (date(2021, 2, 1), date(2021, 2, 28)));
+        let grid = get_month_grid(2026, 2);
+        assert_eq!(grid.len(), 5);
+        assert_eq!((grid[0][0], grid[4][6]), (date(2026, 1, 26), date(2026, 3, 1)));
+    }
+
+    #[test]
+    fn month_grid_rejects_invalid_months() {
+        assert!(get_month_grid(2026, 0).is_empty());
+        assert!(get_month_grid(2026, 13).is_empty());
+    }
+}
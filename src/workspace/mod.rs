@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::generate_id;
+
+/// How panes are laid out next to each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitDirection {
+    /// Side by side
+    #[default]
+    Horizontal,
+    /// Stacked
+    Vertical,
+}
+
+/// An open page in a pane
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tab {
+    pub id: String,
+    pub page_id: String,
+}
+
+impl Tab {
+    fn new(page_id: &str) -> Self {
+        Self { id: generate_id(), page_id: page_id.to_string() }
+    }
+}
+
+/// A column (or row) of the workspace with its own tabs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pane {
+    pub id: String,
+    pub tabs: Vec<Tab>,
+    /// Index of the tab shown
+    pub active: usize,
+}
+
+impl Pane {
+    fn new() -> Self {
+        Self { id: generate_id(), tabs: Vec::new(), active: 0 }
+    }
+
+    pub fn active_tab(&self) -> Option<&Tab> {
+        self.tabs.get(self.active)
+    }
+
+    pub fn active_page(&self) -> Option<&str> {
+        self.active_tab().map(|t| t.page_id.as_str())
+    }
+
+    fn clamp_active(&mut self) {
+        self.active = self.active.min(self.tabs.len().saturating_sub(1));
+    }
+
+    /// Remove the tab at `index`. Another active tab stays active; removing
+    /// the active tab shows the one after it, or the previous one if it was last.
+    fn remove_tab(&mut self, index: usize) -> Tab {
+        let tab = self.tabs.remove(index);
+        if index < self.active || self.active >= self.tabs.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        self.clamp_active();
+        tab
+    }
+}
+
+/// Open tabs and split panes. The focused pane follows the URL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub panes: Vec<Pane>,
+    pub direction: SplitDirection,
+    /// Index of the pane receiving navigation
+    pub focused: usize,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self { panes: vec![Pane::new()], direction: SplitDirection::default(), focused: 0 }
+    }
+}
+
+impl Workspace {
+    pub fn focused_pane(&self) -> Option<&Pane> {
+        self.panes.get(self.focused)
+    }
+
+    pub fn pane_index(&self, pane_id: &str) -> Option<usize> {
+        self.panes.iter().position(|p| p.id == pane_id)
+    }
+
+    pub fn focus(&mut self, pane: usize) {
+        if pane < self.panes.len() {
+            self.focused = pane;
+        }
+    }
+
+    /// Show a page in the focused pane: switch to its tab if open there,
+    /// otherwise replace the active tab's page (or open the first tab)
+    pub fn navigate(&mut self, page_id: &str) {
+        let focused = self.focused;
+        let pane = match self.panes.get_mut(focused) {
+            Some(pane) => pane,
+            None => return,
+        };
+        if let Some(i) = pane.tabs.iter().position(|t| t.page_id == page_id) {
+            pane.active = i;
+        } else if let Some(tab) = pane.tabs.get_mut(pane.active) {
+            tab.page_id = page_id.to_string();
+        } else {
+            pane.tabs.push(Tab::new(page_id));
+            pane.active = pane.tabs.len() - 1;
+        }
+    }
+
+    /// Open a page in a new tab of `pane` and show it
+    pub fn open_tab(&mut self, pane: usize, page_id: &str) {
+        if let Some(pane) = self.panes.get_mut(pane) {
+            pane.tabs.push(Tab::new(page_id));
+            pane.active = pane.tabs.len() - 1;
+        }
+    }
+
+    /// Add a pane after `after` showing `page_id`; returns its index
+    pub fn split(&mut self, after: usize, page_id: Option<&str>) -> usize {
+        let mut pane = Pane::new();
+        if let Some(page_id) = page_id {
+            pane.tabs.push(Tab::new(page_id));
+        }
+        let index = (after + 1).min(self.panes.len());
+        self.panes.insert(index, pane);
+        if self.focused >= index {
+            self.focused += 1;
+        }
+        index
+    }
+
+    /// Open a page in the pane after the focused one, splitting if there is none.
+    /// Focus stays where it was.
+    pub fn open_in_split(&mut self, page_id: &str) -> usize {
+        let target = self.focused + 1;
+        if target < self.panes.len() {
+            let pane = &mut self.panes[target];
+            match pane.tabs.iter().position(|t| t.page_id == page_id) {
+                Some(i) => pane.active = i,
+                None => self.open_tab(target, page_id),
+            }
+            target
+        } else {
+            self.split(self.focused, Some(page_id))
+        }
+    }
+
+    /// Close a tab; an emptied pane closes too unless it is the last one
+    pub fn close_tab(&mut self, pane: usize, tab_id: &str) {
+        let emptied = match self.panes.get_mut(pane) {
+            Some(p) => {
+                if let Some(i) = p.tabs.iter().position(|t| t.id == tab_id) {
+                    p.remove_tab(i);
+                }
+                p.tabs.is_empty()
+            }
+            None => return,
+        };
+        if emptied {
+            self.close_pane(pane);
+        }
+    }
+
+    pub fn close_pane(&mut self, pane: usize) {
+        if self.panes.len() <= 1 || pane >= self.panes.len() {
+            return;
+        }
+        self.panes.remove(pane);
+        if self.focused > pane || self.focused >= self.panes.len() {
+            self.focused = self.focused.saturating_sub(1);
+        }
+    }
+
+    /// Move a tab to `index` in another (or the same) pane and show it there
+    pub fn move_tab(&mut self, from: usize, tab_id: &str, to: usize, index: usize) {
+        if from >= self.panes.len() || to >= self.panes.len() {
+            return;
+        }
+        let position = match self.panes[from].tabs.iter().position(|t| t.id == tab_id) {
+            Some(i) => i,
+            None => return,
+        };
+        let tab = self.panes[from].remove_tab(position);
+
+        let dest = &mut self.panes[to];
+        let index = index.min(dest.tabs.len());
+        dest.tabs.insert(index, tab);
+        dest.active = index;
+        self.focused = to;
+
+        if self.panes[from].tabs.is_empty() && from != to {
+            self.close_pane(from);
+        }
+    }
+
+    /// Pages open in any tab, each once, in pane and tab order
+    pub fn page_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        for tab in self.panes.iter().flat_map(|p| &p.tabs) {
+            if !ids.contains(&tab.page_id) {
+                ids.push(tab.page_id.clone());
+            }
+        }
+        ids
+    }
+
+    /// Drop tabs of pages that no longer exist. Only call this once the
+    /// pages the tabs point to are loaded.
+    pub fn retain_pages(&mut self, exists: impl Fn(&str) -> bool) {
+        for pane in &mut self.panes {
+            pane.tabs.retain(|t| exists(&t.page_id));
+            pane.clamp_active();
+        }
+        while self.panes.len() > 1 {
+            match self.panes.iter().position(|p| p.tabs.is_empty()) {
+                Some(i) => self.close_pane(i),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two panes: `a b c` with `c` active, and `x`
+    fn workspace() -> Workspace {
+        let mut workspace = Workspace::default();
+        for page in ["a", "b", "c"] {
+            workspace.open_tab(0, page);
+        }
+        workspace.split(0, Some("x"));
+        workspace
+    }
+
+    fn tab_id(workspace: &Workspace, pane: usize, page: &str) -> String {
+        workspace.panes[pane].tabs.iter().find(|t| t.page_id == page).unwrap().id.clone()
+    }
+
+    #[test]
+    fn moving_a_tab_before_the_active_one_keeps_it_shown() {
+        let mut workspace = workspace();
+        let a = tab_id(&workspace, 0, "a");
+        workspace.move_tab(0, &a, 1, 1);
+
+        assert_eq!(workspace.panes[0].active_page(), Some("c"));
+        assert_eq!(workspace.panes[1].active_page(), Some("a"));
+        assert_eq!(workspace.focused, 1);
+    }
+
+    #[test]
+    fn moving_the_active_tab_shows_a_neighbour() {
+        let mut workspace = workspace();
+        workspace.panes[0].active = 1;
+        let b = tab_id(&workspace, 0, "b");
+        workspace.move_tab(0, &b, 1, 0);
+        assert_eq!(workspace.panes[0].active_page(), Some("c"));
+        assert_eq!(workspace.panes[1].active_page(), Some("b"));
+
+        let c = tab_id(&workspace, 0, "c");
+        workspace.move_tab(0, &c, 1, 0);
+        assert_eq!(workspace.panes[0].active_page(), Some("a"));
+    }
+
+    #[test]
+    fn moving_within_a_pane_shows_the_moved_tab() {
+        let mut workspace = workspace();
+        let a = tab_id(&workspace, 0, "a");
+        workspace.move_tab(0, &a, 0, 2);
+
+        let order: Vec<&str> = workspace.panes[0].tabs.iter().map(|t| t.page_id.as_str()).collect();
+        assert_eq!(order, vec!["b", "c", "a"]);
+        assert_eq!(workspace.panes[0].active_page(), Some("a"));
+    }
+
+    #[test]
+    fn moving_the_last_tab_closes_its_pane() {
+        let mut workspace = workspace();
+        let x = tab_id(&workspace, 1, "x");
+        workspace.move_tab(1, &x, 0, 0);
+
+        assert_eq!(workspace.panes.len(), 1);
+        assert_eq!(workspace.panes[0].active_page(), Some("x"));
+        assert_eq!(workspace.focused, 0);
+    }
+
+    #[test]
+    fn closing_a_tab_before_the_active_one_keeps_it_shown() {
+        let mut workspace = workspace();
+        let a = tab_id(&workspace, 0, "a");
+        workspace.close_tab(0, &a);
+        assert_eq!(workspace.panes[0].active_page(), Some("c"));
+    }
+
+    #[test]
+    fn tabs_of_missing_pages_are_dropped() {
+        let mut workspace = workspace();
+        workspace.open_tab(1, "a");
+        assert_eq!(workspace.page_ids(), ["a", "b", "c", "x"]);
+
+        workspace.retain_pages(|id| id != "c" && id != "x");
+        assert_eq!(workspace.page_ids(), ["a", "b"]);
+        assert_eq!(workspace.panes.len(), 2);
+        assert_eq!(workspace.panes[0].active_page(), Some("b"));
+
+        workspace.retain_pages(|id| id == "b");
+        assert_eq!(workspace.panes.len(), 1);
+        assert_eq!(workspace.panes[0].active_page(), Some("b"));
+    }
+}